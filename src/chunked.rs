@@ -0,0 +1,217 @@
+use crate::parser::ParseError;
+use crate::types::Header;
+
+const MAX_LINE_LENGTH: usize = 4096;
+const FORBIDDEN_TRAILERS: [&str; 6] = [
+    Header::CONTENT_LENGTH,
+    Header::TRANSFER_ENCODING,
+    Header::HOST,
+    Header::CONTENT_TYPE,
+    Header::CONTENT_ENCODING,
+    Header::TRAILER,
+];
+
+// State
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Size,
+    Data(usize),
+    DataEnd,
+    Trailers,
+    Done,
+}
+
+// ChunkedDecoder
+#[derive(Debug)]
+pub struct ChunkedDecoder {
+    state: State,
+    body: Vec<u8>,
+    trailers: Vec<Header>,
+    max_trailers: usize,
+    max_trailer_bytes: usize,
+    trailer_bytes: usize,
+}
+impl Default for ChunkedDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl ChunkedDecoder {
+    pub fn new() -> Self {
+        Self::with_limits(usize::MAX, usize::MAX)
+    }
+    pub fn with_limits(max_trailers: usize, max_trailer_bytes: usize) -> Self {
+        Self {
+            state: State::Size,
+            body: Vec::new(),
+            trailers: Vec::new(),
+            max_trailers,
+            max_trailer_bytes,
+            trailer_bytes: 0,
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.state == State::Done
+    }
+    pub fn body_length(&self) -> usize {
+        self.body.len()
+    }
+    pub fn finish(self) -> (Vec<u8>, Vec<Header>) {
+        (self.body, self.trailers)
+    }
+
+    pub fn decode(&mut self, buffer: &[u8]) -> Result<usize, ParseError> {
+        let mut consumed = 0;
+        loop {
+            let remaining = &buffer[consumed..];
+            match self.state {
+                State::Done => return Ok(consumed),
+                State::Data(size) => {
+                    if remaining.is_empty() {
+                        return Ok(consumed);
+                    }
+                    let take = size.min(remaining.len());
+                    self.body.extend_from_slice(&remaining[..take]);
+                    consumed += take;
+                    self.state = match size - take {
+                        0 => State::DataEnd,
+                        left => State::Data(left),
+                    };
+                }
+                State::DataEnd => {
+                    if remaining.len() < 2 {
+                        return Ok(consumed);
+                    }
+                    if &remaining[..2] != b"\r\n" {
+                        return Err(ParseError::MalformedChunk);
+                    }
+                    consumed += 2;
+                    self.state = State::Size;
+                }
+                State::Size => {
+                    let Some(line) = read_line(remaining)? else {
+                        return Ok(consumed);
+                    };
+                    consumed += line.len() + 2;
+                    let size = parse_chunk_size(line)?;
+                    self.state = match size {
+                        0 => State::Trailers,
+                        size => State::Data(size),
+                    };
+                }
+                State::Trailers => {
+                    let line = read_line(remaining)?;
+                    let length = line.map_or(remaining.len(), |line| line.len() + 2);
+                    if self.trailer_bytes + length > self.max_trailer_bytes {
+                        return Err(ParseError::HeadersTooLarge);
+                    }
+                    let Some(line) = line else {
+                        return Ok(consumed);
+                    };
+                    consumed += line.len() + 2;
+                    self.trailer_bytes += line.len() + 2;
+                    if line.is_empty() {
+                        self.state = State::Done;
+                        continue;
+                    }
+                    if self.trailers.len() == self.max_trailers {
+                        return Err(ParseError::HeadersTooLarge);
+                    }
+                    let trailer = parse_trailer(line)?;
+                    let forbidden = FORBIDDEN_TRAILERS
+                        .iter()
+                        .any(|name| trailer.name().eq_ignore_ascii_case(name));
+                    if !forbidden {
+                        self.trailers.push(trailer);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn read_line(buffer: &[u8]) -> Result<Option<&[u8]>, ParseError> {
+    match buffer.windows(2).position(|window| window == b"\r\n") {
+        Some(index) => Ok(Some(&buffer[..index])),
+        None if buffer.len() > MAX_LINE_LENGTH => Err(ParseError::MalformedChunk),
+        None => Ok(None),
+    }
+}
+
+fn parse_chunk_size(line: &[u8]) -> Result<usize, ParseError> {
+    let line = std::str::from_utf8(line).map_err(|_| ParseError::MalformedChunk)?;
+    let size = match line.split_once(';') {
+        Some((size, _extensions)) => size,
+        None => line,
+    };
+    let size = size.trim_end_matches([' ', '\t']);
+    if size.is_empty() || !size.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err(ParseError::MalformedChunk);
+    }
+    usize::from_str_radix(size, 16).map_err(|_| ParseError::MalformedChunk)
+}
+
+fn parse_trailer(line: &[u8]) -> Result<Header, ParseError> {
+    let line = std::str::from_utf8(line).map_err(|_| ParseError::MalformedChunk)?;
+    let (field_name, field_value) = line.split_once(':').ok_or(ParseError::MalformedChunk)?;
+    if field_name.is_empty() || field_name.contains([' ', '\t']) {
+        return Err(ParseError::MalformedChunk);
+    }
+    Ok(Header::new(field_name, field_value.trim()))
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::ChunkedDecoder;
+    use crate::parser::ParseError;
+    use crate::types::Header;
+
+    #[test]
+    pub fn decode_chunks_with_extensions_and_trailers() {
+        let bytes = b"4;name=value\r\nWiki\r\n5\r\npedia\r\n0\r\nExpires: never\r\nContent-Length: 9\r\n\r\nnext";
+        let mut decoder = ChunkedDecoder::new();
+        let consumed = decoder.decode(bytes).unwrap();
+        assert!(decoder.is_done());
+        assert_eq!(&bytes[consumed..], b"next");
+        let (body, trailers) = decoder.finish();
+        assert_eq!(body, b"Wikipedia");
+        assert_eq!(trailers, vec![Header::new("Expires", "never")]);
+    }
+
+    #[test]
+    pub fn decode_across_reads() {
+        let bytes = b"a\r\n0123456789\r\n0\r\n\r\n";
+        let mut decoder = ChunkedDecoder::new();
+        let mut buffer = Vec::new();
+        for byte in bytes {
+            buffer.push(*byte);
+            let consumed = decoder.decode(&buffer).unwrap();
+            buffer.drain(..consumed);
+        }
+        assert!(decoder.is_done());
+        assert_eq!(decoder.finish().0, b"0123456789");
+    }
+
+    #[test]
+    pub fn limit_trailers() {
+        let mut decoder = ChunkedDecoder::with_limits(1, 64);
+        let bytes = b"0\r\nA: 1\r\nB: 2\r\n\r\n";
+        assert_eq!(decoder.decode(bytes), Err(ParseError::HeadersTooLarge));
+        let mut decoder = ChunkedDecoder::with_limits(8, 16);
+        let bytes = format!("0\r\nA: {}", "a".repeat(16));
+        assert_eq!(
+            decoder.decode(bytes.as_bytes()),
+            Err(ParseError::HeadersTooLarge)
+        );
+    }
+
+    #[test]
+    pub fn reject_malformed_chunks() {
+        for bytes in [&b"x\r\n"[..], b"3\r\nabcd\r\n", b"ffffffffffffffffff\r\n"] {
+            let mut decoder = ChunkedDecoder::new();
+            assert_eq!(decoder.decode(bytes), Err(ParseError::MalformedChunk));
+        }
+    }
+}
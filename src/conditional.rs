@@ -0,0 +1,331 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::date::{format_http_date, parse_http_date};
+use crate::middleware::{Middleware, Next};
+use crate::types::{Header, Method, Request, Response, ResponseCode};
+
+// EntityTag
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityTag {
+    pub weak: bool,
+    pub tag: String,
+}
+impl EntityTag {
+    pub fn strong(tag: &str) -> Self {
+        Self {
+            weak: false,
+            tag: tag.to_string(),
+        }
+    }
+    pub fn weak(tag: &str) -> Self {
+        Self {
+            weak: true,
+            tag: tag.to_string(),
+        }
+    }
+    pub fn from_body(body: &[u8], weak: bool) -> Self {
+        let mut hasher = DefaultHasher::new();
+        body.hash(&mut hasher);
+        Self {
+            weak,
+            tag: format!("{:x}-{:016x}", body.len(), hasher.finish()),
+        }
+    }
+
+    pub fn parse(string: &str) -> Option<Self> {
+        let string = string.trim();
+        let (weak, opaque) = match string.strip_prefix("W/") {
+            Some(opaque) => (true, opaque),
+            None => (false, string),
+        };
+        let tag = opaque.strip_prefix('"')?.strip_suffix('"')?;
+        if tag.contains('"') {
+            return None;
+        }
+        Some(Self {
+            weak,
+            tag: tag.to_string(),
+        })
+    }
+    pub fn parse_list(string: &str) -> Vec<Self> {
+        string.split(',').filter_map(Self::parse).collect()
+    }
+
+    pub fn strong_eq(&self, other: &Self) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+    pub fn weak_eq(&self, other: &Self) -> bool {
+        self.tag == other.tag
+    }
+}
+impl Display for EntityTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            write!(f, "W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+// Validators
+#[derive(Debug, Clone, Default)]
+pub struct Validators {
+    pub etag: Option<EntityTag>,
+    pub last_modified: Option<SystemTime>,
+}
+impl Validators {
+    pub fn from_response(response: &Response) -> Self {
+        Self {
+            etag: response.header(Header::ETAG).and_then(EntityTag::parse),
+            last_modified: response
+                .header(Header::LAST_MODIFIED)
+                .and_then(parse_http_date),
+        }
+    }
+
+    pub fn apply(&self, response: &mut Response) {
+        if let Some(etag) = &self.etag {
+            response.set_header(Header::ETAG, &etag.to_string());
+        }
+        if let Some(last_modified) = self.last_modified {
+            response.set_header(Header::LAST_MODIFIED, &format_http_date(last_modified));
+        }
+    }
+
+    pub fn evaluate(&self, request: &Request) -> Option<ResponseCode> {
+        let safe = matches!(request.request_line.method, Method::Get | Method::Head);
+        if let Some(if_match) = request.header(Header::IF_MATCH) {
+            if !self.matches(if_match, EntityTag::strong_eq) {
+                return Some(ResponseCode::PreconditionFailed);
+            }
+        } else if let Some(since) = request
+            .header(Header::IF_UNMODIFIED_SINCE)
+            .and_then(parse_http_date)
+        {
+            if self.modified_since(since) == Some(true) {
+                return Some(ResponseCode::PreconditionFailed);
+            }
+        }
+        if let Some(if_none_match) = request.header(Header::IF_NONE_MATCH) {
+            if self.matches(if_none_match, EntityTag::weak_eq) {
+                return Some(match safe {
+                    true => ResponseCode::NotModified,
+                    false => ResponseCode::PreconditionFailed,
+                });
+            }
+        } else if let Some(since) = request
+            .header(Header::IF_MODIFIED_SINCE)
+            .and_then(parse_http_date)
+        {
+            if safe && self.modified_since(since) == Some(false) {
+                return Some(ResponseCode::NotModified);
+            }
+        }
+        None
+    }
+
+    pub fn if_range(&self, request: &Request) -> bool {
+        let Some(if_range) = request.header(Header::IF_RANGE) else {
+            return true;
+        };
+        if let Some(tag) = EntityTag::parse(if_range) {
+            return self.etag.as_ref().is_some_and(|etag| etag.strong_eq(&tag));
+        }
+        match (parse_http_date(if_range), self.last_modified) {
+            (Some(date), Some(last_modified)) => truncate(last_modified) == date,
+            _ => false,
+        }
+    }
+
+    fn matches(&self, header: &str, compare: fn(&EntityTag, &EntityTag) -> bool) -> bool {
+        if header.trim() == "*" {
+            return true;
+        }
+        let Some(etag) = &self.etag else {
+            return false;
+        };
+        EntityTag::parse_list(header)
+            .iter()
+            .any(|tag| compare(etag, tag))
+    }
+
+    fn modified_since(&self, date: SystemTime) -> Option<bool> {
+        self.last_modified
+            .map(|last_modified| truncate(last_modified) > date)
+    }
+}
+
+fn truncate(time: SystemTime) -> SystemTime {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    UNIX_EPOCH + Duration::from_secs(seconds)
+}
+
+pub fn precondition_response(mut response: Response, response_code: ResponseCode) -> Response {
+    if response_code == ResponseCode::NotModified {
+        for field_name in [
+            Header::CONTENT_TYPE,
+            Header::CONTENT_LENGTH,
+            Header::CONTENT_RANGE,
+        ] {
+            response.remove_header(field_name);
+        }
+    } else {
+        response.remove_header(Header::CONTENT_RANGE);
+    }
+    response.set_response_code(response_code);
+    response.set_body(Vec::new());
+    response
+}
+
+// ETags
+type Lookup = Box<dyn Fn(&Request) -> Option<Validators> + Send + Sync>;
+
+pub struct ETags {
+    weak: bool,
+    lookup: Option<Lookup>,
+}
+impl ETags {
+    pub fn strong() -> Self {
+        Self {
+            weak: false,
+            lookup: None,
+        }
+    }
+    pub fn weak() -> Self {
+        Self {
+            weak: true,
+            lookup: None,
+        }
+    }
+    pub fn validators<F>(mut self, lookup: F) -> Self
+    where
+        F: Fn(&Request) -> Option<Validators> + Send + Sync + 'static,
+    {
+        self.lookup = Some(Box::new(lookup));
+        self
+    }
+}
+impl Middleware for ETags {
+    fn call(&self, mut request: Request, next: &Next) -> Response {
+        let current = self.lookup.as_ref().and_then(|lookup| lookup(&request));
+        if let Some(response_code) = current.and_then(|validators| validators.evaluate(&request)) {
+            let response = Response::from_code(request.request_line.version, response_code.clone());
+            return precondition_response(response, response_code);
+        }
+        if !matches!(request.request_line.method, Method::Get | Method::Head) {
+            return next.run(request);
+        }
+        let body = std::mem::take(&mut request.body);
+        let conditional = request.clone();
+        request.body = body;
+        let mut response = next.run(request);
+        if response.response_code() != &ResponseCode::Ok || response.is_streaming() {
+            return response;
+        }
+        if !response.has_header(Header::ETAG) {
+            let etag = EntityTag::from_body(response.body(), self.weak);
+            response.set_header(Header::ETAG, &etag.to_string());
+        }
+        match Validators::from_response(&response).evaluate(&conditional) {
+            Some(response_code) => precondition_response(response, response_code),
+            None => response,
+        }
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{ETags, EntityTag, Validators};
+    use crate::handler::Handler;
+    use crate::middleware::Stack;
+    use crate::types::{test_request, Request, Response, ResponseCode};
+
+    #[test]
+    pub fn parse_entity_tags() {
+        assert_eq!(EntityTag::parse("\"abc\""), Some(EntityTag::strong("abc")));
+        assert_eq!(EntityTag::parse("W/\"abc\""), Some(EntityTag::weak("abc")));
+        assert_eq!(EntityTag::parse("abc"), None);
+        assert_eq!(EntityTag::weak("abc").to_string(), "W/\"abc\"");
+        assert!(!EntityTag::weak("a").strong_eq(&EntityTag::strong("a")));
+        assert!(EntityTag::weak("a").weak_eq(&EntityTag::strong("a")));
+    }
+
+    #[test]
+    pub fn evaluate_preconditions() {
+        let validators = Validators {
+            etag: Some(EntityTag::strong("v1")),
+            last_modified: Some(UNIX_EPOCH + Duration::from_secs(784111777)),
+        };
+        let date = "Sun, 06 Nov 1994 08:49:37 GMT";
+
+        let get = test_request("GET", "/", &[("If-None-Match", "\"v0\", W/\"v1\"")]);
+        assert_eq!(validators.evaluate(&get), Some(ResponseCode::NotModified));
+        let put = test_request("PUT", "/", &[("If-None-Match", "*")]);
+        assert_eq!(
+            validators.evaluate(&put),
+            Some(ResponseCode::PreconditionFailed)
+        );
+        let put = test_request("PUT", "/", &[("If-Match", "\"v2\"")]);
+        assert_eq!(
+            validators.evaluate(&put),
+            Some(ResponseCode::PreconditionFailed)
+        );
+        let get = test_request("GET", "/", &[("If-Modified-Since", date)]);
+        assert_eq!(validators.evaluate(&get), Some(ResponseCode::NotModified));
+        let get = test_request("GET", "/", &[("If-Unmodified-Since", date)]);
+        assert_eq!(validators.evaluate(&get), None);
+        let etag_only = Validators {
+            etag: Some(EntityTag::strong("v1")),
+            last_modified: None,
+        };
+        assert_eq!(etag_only.evaluate(&get), None);
+        let get = test_request("GET", "/", &[("If-Range", "W/\"v1\"")]);
+        assert!(!validators.if_range(&get));
+    }
+
+    #[test]
+    pub fn check_preconditions_before_unsafe_methods() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let handler = move |request: Request| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Response::new(
+                request.request_line.version,
+                ResponseCode::Ok,
+                b"v2".to_vec(),
+            )
+        };
+        let stack = Stack::new(handler).with(ETags::strong().validators(|_: &Request| {
+            Some(Validators {
+                etag: Some(EntityTag::strong("v1")),
+                last_modified: None,
+            })
+        }));
+
+        let response = stack.handle(test_request("PUT", "/", &[("If-Match", "\"v0\"")]));
+        assert_eq!(response.response_code(), &ResponseCode::PreconditionFailed);
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+        let response = stack.handle(test_request("PUT", "/", &[("If-Match", "\"v1\"")]));
+        assert_eq!(response.response_code(), &ResponseCode::Ok);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let stack = Stack::new(|request: Request| {
+            Response::new(request.request_line.version, ResponseCode::Ok, Vec::new())
+        })
+        .with(ETags::strong());
+        let response = stack.handle(test_request("PUT", "/", &[("If-Match", "\"v0\"")]));
+        assert_eq!(response.response_code(), &ResponseCode::Ok);
+        assert!(!response.has_header("ETag"));
+    }
+}
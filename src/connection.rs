@@ -0,0 +1,426 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use crate::parser::{Limits, ParseError, ParseMode, Parser, Status};
+use crate::types::{Header, HttpVersion, Request, Response, ResponseCode};
+
+const READ_SIZE: usize = 512;
+
+// ReadError
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    Invalid(ParseError),
+}
+impl From<io::Error> for ReadError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+// Timeouts
+#[derive(Debug, Clone)]
+pub struct Timeouts {
+    pub idle: Option<Duration>,
+    pub header: Option<Duration>,
+    pub body: Option<Duration>,
+}
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            idle: Some(Duration::from_secs(5)),
+            header: Some(Duration::from_secs(10)),
+            body: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+// ReadTimeout
+pub trait ReadTimeout {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()>;
+}
+impl ReadTimeout for TcpStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+impl ReadTimeout for &[u8] {
+    fn set_read_timeout(&mut self, _: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Connection
+pub struct Connection<S> {
+    stream: S,
+    buffer: Vec<u8>,
+    parser: Parser,
+    timeouts: Timeouts,
+    requests: usize,
+}
+impl<S: Read + ReadTimeout> Connection<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            buffer: Vec::new(),
+            parser: Parser::new(),
+            timeouts: Timeouts::default(),
+            requests: 0,
+        }
+    }
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.parser = std::mem::take(&mut self.parser).limits(limits);
+        self
+    }
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.parser = std::mem::take(&mut self.parser).mode(mode);
+        self
+    }
+    pub fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    pub fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn read_request(&mut self) -> Result<Option<Request>, ReadError> {
+        let started = Instant::now();
+        let mut header_started = (self.requests == 0).then_some(started);
+        let mut body_started = None;
+        loop {
+            let (consumed, status) = self
+                .parser
+                .parse(&self.buffer)
+                .map_err(ReadError::Invalid)?;
+            self.buffer.drain(..consumed);
+            if let Status::Complete(request) = status {
+                self.requests += 1;
+                return Ok(Some(request));
+            }
+            let idle = self.buffer.is_empty() && self.parser.is_idle();
+            let now = Instant::now();
+            let deadline = if idle && self.requests > 0 {
+                self.timeouts.idle.map(|timeout| started + timeout)
+            } else if self.parser.in_body() {
+                let body_started = *body_started.get_or_insert(now);
+                self.timeouts.body.map(|timeout| body_started + timeout)
+            } else {
+                let header_started = *header_started.get_or_insert(now);
+                self.timeouts.header.map(|timeout| header_started + timeout)
+            };
+            match self.fill(deadline) {
+                Ok(0) if idle => return Ok(None),
+                Ok(0) => return Err(ReadError::Invalid(ParseError::Incomplete)),
+                Ok(_) => (),
+                Err(error) if is_timeout(&error) && idle && self.requests > 0 => return Ok(None),
+                Err(error) if is_timeout(&error) => {
+                    return Err(ReadError::Invalid(ParseError::Timeout))
+                }
+                Err(error) => return Err(ReadError::Io(error)),
+            }
+        }
+    }
+
+    fn fill(&mut self, deadline: Option<Instant>) -> io::Result<usize> {
+        let timeout = match deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    return Err(io::Error::from(ErrorKind::TimedOut));
+                }
+                Some(remaining)
+            }
+            None => None,
+        };
+        self.stream.set_read_timeout(timeout)?;
+        let mut chunk = [0; READ_SIZE];
+        let size = self.stream.read(&mut chunk)?;
+        self.buffer.extend_from_slice(&chunk[..size]);
+        Ok(size)
+    }
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
+pub fn is_persistent(request: &Request) -> bool {
+    if request.close {
+        return false;
+    }
+    let tokens: Vec<String> = request
+        .headers
+        .get_all(Header::CONNECTION)
+        .flat_map(|value| value.split(','))
+        .map(|token| token.trim().to_ascii_lowercase())
+        .collect();
+    if tokens.iter().any(|token| token == "close") {
+        return false;
+    }
+    match request.request_line.version {
+        HttpVersion::HttpV1_0 => tokens.iter().any(|token| token == "keep-alive"),
+        HttpVersion::HttpV1_1 => true,
+        HttpVersion::HttpV2_0 | HttpVersion::HttpV3_0 => false,
+    }
+}
+
+impl<S: Read + Write> Connection<S> {
+    pub fn write_response(&mut self, response: Response, version: &HttpVersion) -> io::Result<()> {
+        write_response(&mut self.stream, response, version)
+    }
+}
+
+pub fn write_response<W: Write>(
+    writer: &mut W,
+    mut response: Response,
+    version: &HttpVersion,
+) -> io::Result<()> {
+    if !response.response_code().is_valid() {
+        return Err(io::Error::new(
+            ErrorKind::InvalidData,
+            format!("invalid status code {}", response.response_code().code()),
+        ));
+    }
+    if !response.response_code().allows_body() {
+        if response.response_code() != &ResponseCode::NotModified {
+            response.remove_header(Header::CONTENT_LENGTH);
+        }
+        response.remove_header(Header::TRANSFER_ENCODING);
+        response.set_body(Vec::new());
+        return response.write_to(writer);
+    }
+    let Some(mut stream) = response.take_stream() else {
+        if !response.has_header(Header::CONTENT_LENGTH)
+            && !response.has_header(Header::TRANSFER_ENCODING)
+        {
+            let length = response.body_length().to_string();
+            response.add_header(Header::CONTENT_LENGTH, &length);
+        }
+        return response.write_to(writer);
+    };
+    response.remove_header(Header::CONTENT_LENGTH);
+    if let Some(length) = stream.length() {
+        response.set_header(Header::CONTENT_LENGTH, &length.to_string());
+        writer.write_all(response.head_string().as_bytes())?;
+        let mut written = 0;
+        for chunk in stream {
+            let chunk = chunk?;
+            written += chunk.len() as u64;
+            writer.write_all(&chunk)?;
+        }
+        if written != length {
+            return Err(io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "body stream does not match its length",
+            ));
+        }
+        return writer.flush();
+    }
+    if *version == HttpVersion::HttpV1_0 {
+        response.set_header(Header::CONNECTION, "close");
+        writer.write_all(response.head_string().as_bytes())?;
+        for chunk in stream {
+            writer.write_all(&chunk?)?;
+        }
+        return writer.flush();
+    }
+    response.set_header(Header::TRANSFER_ENCODING, "chunked");
+    writer.write_all(response.head_string().as_bytes())?;
+    for chunk in stream.by_ref() {
+        let chunk = chunk?;
+        if chunk.is_empty() {
+            continue;
+        }
+        write!(writer, "{:x}\r\n", chunk.len())?;
+        writer.write_all(&chunk)?;
+        writer.write_all(b"\r\n")?;
+    }
+    let mut last_chunk = String::from("0\r\n");
+    for trailer in stream.take_trailers() {
+        last_chunk += &format!("{}\r\n", trailer);
+    }
+    writer.write_all((last_chunk + "\r\n").as_bytes())?;
+    writer.flush()
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use std::io::{ErrorKind, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+
+    use super::{is_persistent, write_response, Connection, ReadError, Timeouts};
+    use crate::parser::{ParseError, ParseMode};
+    use crate::types::{BodyStream, Header, HttpVersion, Response, ResponseCode};
+
+    #[test]
+    pub fn read_body_by_content_length() {
+        let bytes = b"POST /upload HTTP/1.1\r\nContent-Length: 11\r\n\r\nhello world";
+        let mut connection = Connection::new(&bytes[..]);
+        let request = connection.read_request().unwrap().unwrap();
+        assert_eq!(request.body, b"hello world");
+        assert!(connection.read_request().unwrap().is_none());
+    }
+
+    #[test]
+    pub fn read_chunked_body() {
+        let bytes = b"PUT /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6;ext\r\n world\r\n0\r\nChecksum: abc\r\n\r\n";
+        let mut connection = Connection::new(&bytes[..]);
+        let request = connection.read_request().unwrap().unwrap();
+        assert_eq!(request.body, b"hello world");
+        assert_eq!(request.header("Checksum"), None);
+        assert_eq!(request.trailers.get("Checksum"), Some("abc"));
+    }
+
+    #[test]
+    pub fn reject_ambiguous_framing() {
+        for (bytes, response_code) in [
+            (
+                &b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n"[..],
+                ResponseCode::BadRequest,
+            ),
+            (
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, gzip\r\n\r\n",
+                ResponseCode::BadRequest,
+            ),
+            (
+                b"POST / HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n",
+                ResponseCode::BadRequest,
+            ),
+            (
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n",
+                ResponseCode::NotImplemented,
+            ),
+        ] {
+            let mut connection = Connection::new(bytes);
+            match connection.read_request() {
+                Err(ReadError::Invalid(error)) => assert_eq!(error.response_code(), response_code),
+                other => panic!("unexpected result {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    pub fn close_after_lenient_framing() {
+        let bytes = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n0\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        let mut connection = Connection::new(&bytes[..]).mode(ParseMode::Lenient);
+        let request = connection.read_request().unwrap().unwrap();
+        assert!(request.close);
+        assert!(!is_persistent(&request));
+    }
+
+    #[test]
+    pub fn read_pipelined_requests() {
+        let bytes = b"POST /a HTTP/1.1\r\nContent-Length: 3\r\n\r\nabcGET /b HTTP/1.0\r\nConnection: keep-alive\r\n\r\nGET /c HTTP/1.1\r\nConnection: close\r\n\r\n";
+        let mut connection = Connection::new(&bytes[..]);
+        let first = connection.read_request().unwrap().unwrap();
+        assert_eq!(first.request_line.resource, "/a");
+        assert_eq!(first.body, b"abc");
+        assert!(is_persistent(&first));
+        let second = connection.read_request().unwrap().unwrap();
+        assert_eq!(second.request_line.resource, "/b");
+        assert!(is_persistent(&second));
+        let third = connection.read_request().unwrap().unwrap();
+        assert_eq!(third.request_line.resource, "/c");
+        assert!(!is_persistent(&third));
+        assert!(connection.read_request().unwrap().is_none());
+    }
+
+    #[test]
+    pub fn reject_bad_content_length() {
+        for bytes in [
+            &b"POST / HTTP/1.1\r\nContent-Length: 1x\r\n\r\n"[..],
+            b"POST / HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+            b"POST / HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 3\r\n\r\nabc",
+            b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort",
+        ] {
+            let mut connection = Connection::new(bytes);
+            match connection.read_request() {
+                Err(ReadError::Invalid(error)) => {
+                    assert_eq!(error.response_code(), ResponseCode::BadRequest)
+                }
+                other => panic!("unexpected result {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    pub fn write_streaming_response() {
+        let stream =
+            BodyStream::from_chunks(vec![b"hello ".to_vec(), Vec::new(), b"world".to_vec()])
+                .trailers(|| vec![Header::new("Checksum", "abc")]);
+        let response = Response::streaming(HttpVersion::HttpV1_1, ResponseCode::Ok, stream);
+        let mut output = Vec::new();
+        write_response(&mut output, response, &HttpVersion::HttpV1_1).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n6\r\nhello \r\n5\r\nworld\r\n0\r\nChecksum: abc\r\n\r\n"
+        );
+
+        let stream = BodyStream::from_reader(&b"close delimited"[..]);
+        let response = Response::streaming(HttpVersion::HttpV1_0, ResponseCode::Ok, stream);
+        let mut output = Vec::new();
+        write_response(&mut output, response, &HttpVersion::HttpV1_0).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.0 200 OK\r\nConnection: close\r\n\r\nclose delimited"
+        );
+
+        let stream = BodyStream::from_reader(&b"sized"[..]).sized(5);
+        let response = Response::streaming(HttpVersion::HttpV1_1, ResponseCode::Ok, stream);
+        let mut output = Vec::new();
+        write_response(&mut output, response, &HttpVersion::HttpV1_1).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nsized"
+        );
+    }
+
+    #[test]
+    pub fn omit_length_without_body() {
+        for response_code in [ResponseCode::NoContent, ResponseCode::NotModified] {
+            let response = Response::new(HttpVersion::HttpV1_1, response_code, Vec::new());
+            let mut output = Vec::new();
+            write_response(&mut output, response, &HttpVersion::HttpV1_1).unwrap();
+            let output = String::from_utf8(output).unwrap();
+            assert!(output.ends_with("\r\n\r\n"));
+            assert!(!output.contains(Header::CONTENT_LENGTH));
+        }
+    }
+
+    #[test]
+    pub fn refuse_invalid_status_codes() {
+        for response_code in [
+            ResponseCode::new(799),
+            ResponseCode::Other(42, "Odd".into()),
+        ] {
+            let response = Response::new(HttpVersion::HttpV1_1, response_code, Vec::new());
+            let mut output = Vec::new();
+            let error = write_response(&mut output, response, &HttpVersion::HttpV1_1).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+            assert!(output.is_empty());
+        }
+    }
+
+    #[test]
+    pub fn time_out_slow_headers() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        client.write_all(b"GET / HTTP/1.1\r\nHost: a\r\n").unwrap();
+        let timeouts = Timeouts {
+            idle: None,
+            header: Some(Duration::from_millis(50)),
+            body: None,
+        };
+        let mut connection = Connection::new(server).timeouts(timeouts);
+        assert!(matches!(
+            connection.read_request(),
+            Err(ReadError::Invalid(ParseError::Timeout))
+        ));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::types::{Request, Response};
+
+// Handler
+pub trait Handler: Send + Sync + 'static {
+    fn handle(&self, request: Request) -> Response;
+    fn try_handle(&self, request: Request) -> Result<Response, Box<Request>> {
+        Ok(self.handle(request))
+    }
+}
+impl<F> Handler for F
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    fn handle(&self, request: Request) -> Response {
+        self(request)
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::Handler;
+    use crate::types::{Request, Response, ResponseCode};
+
+    #[test]
+    pub fn closure_is_handler() {
+        let handler = |request: Request| {
+            Response::new(
+                request.request_line.version,
+                ResponseCode::Ok,
+                request.request_line.resource.into_bytes(),
+            )
+        };
+        let request = Request::parse_from_str("GET /home HTTP/1.1\r\n\r\n").unwrap();
+        let response = handler.handle(request);
+        assert_eq!(response.body_length(), "/home".len());
+    }
+}
@@ -0,0 +1,662 @@
+use std::fmt::{self, Display};
+use std::time::SystemTime;
+
+use crate::date::{format_http_date, parse_http_date};
+use crate::range::ByteRange;
+use crate::types::{is_token, Header, HeaderMap};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+// TypedHeader
+pub trait TypedHeader: Sized {
+    const NAME: &'static str;
+    fn parse(value: &str) -> Option<Self>;
+    fn format(&self) -> String;
+}
+
+impl HeaderMap {
+    pub fn typed<H: TypedHeader>(&self) -> Option<H> {
+        let values: Vec<&str> = self.get_all(H::NAME).collect();
+        if values.is_empty() {
+            return None;
+        }
+        H::parse(&values.join(", "))
+    }
+    pub fn insert_typed<H: TypedHeader>(&mut self, header: &H) {
+        self.insert(H::NAME, &header.format());
+    }
+}
+
+// ContentType
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentType {
+    pub media_type: String,
+    pub params: Vec<(String, String)>,
+}
+impl ContentType {
+    pub fn new(media_type: &str) -> Self {
+        Self {
+            media_type: media_type.to_ascii_lowercase(),
+            params: Vec::new(),
+        }
+    }
+    pub fn param(mut self, name: &str, value: &str) -> Self {
+        self.params
+            .push((name.to_ascii_lowercase(), value.to_string()));
+        self
+    }
+
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(param, _)| param.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+    pub fn charset(&self) -> Option<&str> {
+        self.get_param("charset")
+    }
+}
+impl TypedHeader for ContentType {
+    const NAME: &'static str = Header::CONTENT_TYPE;
+    fn parse(value: &str) -> Option<Self> {
+        let mut parts = split_params(value);
+        let media_type = parts.next()?.trim();
+        let (kind, subtype) = media_type.split_once('/')?;
+        if !is_token(kind) || !is_token(subtype) {
+            return None;
+        }
+        let mut content_type = Self::new(media_type);
+        for part in parts {
+            let (name, value) = parse_param(part)?;
+            content_type = content_type.param(&name, &value);
+        }
+        Some(content_type)
+    }
+    fn format(&self) -> String {
+        let mut value = self.media_type.clone();
+        for (name, param) in &self.params {
+            value += &format!("; {name}={}", quote(param));
+        }
+        value
+    }
+}
+
+// ContentLength
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ContentLength(pub u64);
+impl TypedHeader for ContentLength {
+    const NAME: &'static str = Header::CONTENT_LENGTH;
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        value.parse().ok().map(Self)
+    }
+    fn format(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+// QualityItem
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityItem {
+    pub value: String,
+    pub quality: u16,
+}
+impl QualityItem {
+    pub fn new(value: &str, quality: u16) -> Self {
+        Self {
+            value: value.to_string(),
+            quality: quality.min(1000),
+        }
+    }
+
+    fn parse(item: &str) -> Option<Self> {
+        let mut value = String::new();
+        let mut quality = 1000;
+        for (index, part) in split_params(item).enumerate() {
+            let part = part.trim();
+            if index == 0 {
+                value = part.to_string();
+                continue;
+            }
+            let (name, param) = parse_param(part)?;
+            if name == "q" {
+                quality = parse_quality(&param)?;
+            } else {
+                value += &format!(";{name}={}", quote(&param));
+            }
+        }
+        if value.is_empty() {
+            return None;
+        }
+        Some(Self { value, quality })
+    }
+}
+impl Display for QualityItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.quality {
+            1000 => write!(f, "{}", self.value),
+            0 => write!(f, "{};q=0", self.value),
+            quality => {
+                let decimals = format!("{quality:03}");
+                write!(f, "{};q=0.{}", self.value, decimals.trim_end_matches('0'))
+            }
+        }
+    }
+}
+
+fn parse_quality(value: &str) -> Option<u16> {
+    let (whole, decimals) = value.split_once('.').unwrap_or((value, ""));
+    if decimals.len() > 3 || !decimals.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let decimals: u16 = format!("{decimals:0<3}").parse().ok()?;
+    match whole {
+        "0" => Some(decimals),
+        "1" if decimals == 0 => Some(1000),
+        _ => None,
+    }
+}
+
+fn parse_quality_list(value: &str) -> Option<Vec<QualityItem>> {
+    split_list(value).map(QualityItem::parse).collect()
+}
+
+fn preferred(items: &[QualityItem]) -> Vec<&QualityItem> {
+    let mut items: Vec<&QualityItem> = items.iter().filter(|item| item.quality > 0).collect();
+    items.sort_by_key(|item| std::cmp::Reverse(item.quality));
+    items
+}
+
+fn format_quality_list(items: &[QualityItem]) -> String {
+    items
+        .iter()
+        .map(QualityItem::to_string)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Accept
+#[derive(Debug, Clone, PartialEq)]
+pub struct Accept(pub Vec<QualityItem>);
+impl Accept {
+    pub fn preferred(&self) -> Vec<&QualityItem> {
+        preferred(&self.0)
+    }
+    pub fn quality(&self, media_type: &str) -> u16 {
+        let Some((essence, params)) = media_range(media_type) else {
+            return 0;
+        };
+        let kind = essence.split_once('/').map_or(essence, |(kind, _)| kind);
+        let specificity = |item: &QualityItem| {
+            let (range, range_params) = media_range(&item.value)?;
+            if range == "*/*" {
+                return Some(0);
+            }
+            if range.eq_ignore_ascii_case(&format!("{kind}/*")) {
+                return Some(1);
+            }
+            if !range.eq_ignore_ascii_case(essence) {
+                return None;
+            }
+            match range_params.is_empty() {
+                true => Some(2),
+                false => range_params
+                    .iter()
+                    .all(|param| params.contains(param))
+                    .then_some(3),
+            }
+        };
+        self.0
+            .iter()
+            .filter_map(|item| Some((specificity(item)?, item.quality)))
+            .min_by_key(|(specificity, _)| std::cmp::Reverse(*specificity))
+            .map_or(0, |(_, quality)| quality)
+    }
+}
+impl TypedHeader for Accept {
+    const NAME: &'static str = Header::ACCEPT;
+    fn parse(value: &str) -> Option<Self> {
+        parse_quality_list(value).map(Self)
+    }
+    fn format(&self) -> String {
+        format_quality_list(&self.0)
+    }
+}
+
+// AcceptEncoding
+#[derive(Debug, Clone, PartialEq)]
+pub struct AcceptEncoding(pub Vec<QualityItem>);
+impl AcceptEncoding {
+    pub fn preferred(&self) -> Vec<&QualityItem> {
+        preferred(&self.0)
+    }
+    pub fn quality(&self, coding: &str) -> u16 {
+        let exact = self
+            .0
+            .iter()
+            .find(|item| item.value.eq_ignore_ascii_case(coding));
+        match exact.or_else(|| self.0.iter().find(|item| item.value == "*")) {
+            Some(item) => item.quality,
+            None if coding.eq_ignore_ascii_case("identity") => 1000,
+            None => 0,
+        }
+    }
+}
+impl TypedHeader for AcceptEncoding {
+    const NAME: &'static str = Header::ACCEPT_ENCODING;
+    fn parse(value: &str) -> Option<Self> {
+        parse_quality_list(value).map(Self)
+    }
+    fn format(&self) -> String {
+        format_quality_list(&self.0)
+    }
+}
+
+// Authorization
+#[derive(Debug, Clone, PartialEq)]
+pub enum Authorization {
+    Basic { username: String, password: String },
+    Bearer(String),
+    Other { scheme: String, credentials: String },
+}
+impl Authorization {
+    pub fn basic(username: &str, password: &str) -> Self {
+        Self::Basic {
+            username: username.to_string(),
+            password: password.to_string(),
+        }
+    }
+    pub fn bearer(token: &str) -> Self {
+        Self::Bearer(token.to_string())
+    }
+}
+impl TypedHeader for Authorization {
+    const NAME: &'static str = Header::AUTHORIZATION;
+    fn parse(value: &str) -> Option<Self> {
+        let (scheme, credentials) = value.trim().split_once(' ')?;
+        let credentials = credentials.trim();
+        if !is_token(scheme) || credentials.is_empty() {
+            return None;
+        }
+        if scheme.eq_ignore_ascii_case("Basic") {
+            let decoded = String::from_utf8(base64_decode(credentials)?).ok()?;
+            let (username, password) = decoded.split_once(':')?;
+            return Some(Self::basic(username, password));
+        }
+        if scheme.eq_ignore_ascii_case("Bearer") {
+            return Some(Self::bearer(credentials));
+        }
+        Some(Self::Other {
+            scheme: scheme.to_string(),
+            credentials: credentials.to_string(),
+        })
+    }
+    fn format(&self) -> String {
+        match self {
+            Self::Basic { username, password } => {
+                format!(
+                    "Basic {}",
+                    base64_encode(format!("{username}:{password}").as_bytes())
+                )
+            }
+            Self::Bearer(token) => format!("Bearer {token}"),
+            Self::Other {
+                scheme,
+                credentials,
+            } => format!("{scheme} {credentials}"),
+        }
+    }
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let block = chunk.iter().enumerate().fold(0u32, |block, (index, byte)| {
+            block | (*byte as u32) << (16 - index * 8)
+        });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(BASE64[(block >> (18 - index * 6)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn base64_decode(string: &str) -> Option<Vec<u8>> {
+    let string = string.trim_end_matches('=');
+    let mut decoded = Vec::new();
+    let mut block = 0u32;
+    let mut bits = 0;
+    for byte in string.bytes() {
+        let value = BASE64.iter().position(|digit| *digit == byte)?;
+        block = block << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            decoded.push((block >> bits) as u8);
+        }
+    }
+    Some(decoded)
+}
+
+// Range
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range(pub Vec<ByteRange>);
+impl TypedHeader for Range {
+    const NAME: &'static str = Header::RANGE;
+    fn parse(value: &str) -> Option<Self> {
+        ByteRange::parse(value).map(Self)
+    }
+    fn format(&self) -> String {
+        let ranges: Vec<String> = self.0.iter().map(ByteRange::to_string).collect();
+        format!("bytes={}", ranges.join(","))
+    }
+}
+
+// CacheControl
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheControl {
+    pub directives: Vec<(String, Option<String>)>,
+}
+impl CacheControl {
+    pub fn new() -> Self {
+        Self {
+            directives: Vec::new(),
+        }
+    }
+    pub fn directive(mut self, name: &str, value: Option<&str>) -> Self {
+        self.directives
+            .push((name.to_ascii_lowercase(), value.map(String::from)));
+        self
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.directives
+            .iter()
+            .any(|(directive, _)| directive.eq_ignore_ascii_case(name))
+    }
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.directives
+            .iter()
+            .find(|(directive, _)| directive.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.as_deref())
+    }
+    pub fn max_age(&self) -> Option<u64> {
+        self.get("max-age")?.parse().ok()
+    }
+    pub fn no_cache(&self) -> bool {
+        self.has("no-cache")
+    }
+    pub fn no_store(&self) -> bool {
+        self.has("no-store")
+    }
+}
+impl TypedHeader for CacheControl {
+    const NAME: &'static str = Header::CACHE_CONTROL;
+    fn parse(value: &str) -> Option<Self> {
+        let mut cache_control = Self::new();
+        for directive in split_list(value) {
+            cache_control = match directive.split_once('=') {
+                Some(_) => {
+                    let (name, value) = parse_param(directive)?;
+                    cache_control.directive(&name, Some(&value))
+                }
+                None if is_token(directive) => cache_control.directive(directive, None),
+                None => return None,
+            };
+        }
+        Some(cache_control)
+    }
+    fn format(&self) -> String {
+        self.directives
+            .iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{name}={}", quote(value)),
+                None => name.clone(),
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+// Host
+#[derive(Debug, Clone, PartialEq)]
+pub struct Host {
+    pub hostname: String,
+    pub port: Option<u16>,
+}
+impl Host {
+    pub fn new(hostname: &str, port: Option<u16>) -> Self {
+        Self {
+            hostname: hostname.to_ascii_lowercase(),
+            port,
+        }
+    }
+}
+impl TypedHeader for Host {
+    const NAME: &'static str = Header::HOST;
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let (hostname, port) = match value.strip_prefix('[') {
+            Some(literal) => {
+                let (address, rest) = literal.split_once(']')?;
+                let port = match rest {
+                    "" => None,
+                    rest => Some(rest.strip_prefix(':')?),
+                };
+                (&value[..address.len() + 2], port)
+            }
+            None => match value.split_once(':') {
+                Some((hostname, port)) => (hostname, Some(port)),
+                None => (value, None),
+            },
+        };
+        if hostname.is_empty() || hostname.contains(['/', '?', '#', '@', ' ']) {
+            return None;
+        }
+        let port = match port {
+            Some("") | None => None,
+            Some(port) if port.bytes().all(|byte| byte.is_ascii_digit()) => {
+                Some(port.parse().ok()?)
+            }
+            Some(_) => return None,
+        };
+        Some(Self::new(hostname, port))
+    }
+    fn format(&self) -> String {
+        match self.port {
+            Some(port) => format!("{}:{port}", self.hostname),
+            None => self.hostname.clone(),
+        }
+    }
+}
+
+// Date
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Date(pub SystemTime);
+impl TypedHeader for Date {
+    const NAME: &'static str = Header::DATE;
+    fn parse(value: &str) -> Option<Self> {
+        parse_http_date(value).map(Self)
+    }
+    fn format(&self) -> String {
+        format_http_date(self.0)
+    }
+}
+
+fn split_params(value: &str) -> impl Iterator<Item = &str> {
+    split_unquoted(value, ';')
+}
+
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    split_unquoted(value, ',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+fn split_unquoted(value: &str, separator: char) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    let mut escaped = false;
+    value.split(move |character| {
+        match character {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            _ if character == separator && !quoted => return true,
+            _ => (),
+        }
+        false
+    })
+}
+
+fn media_range(value: &str) -> Option<(&str, Vec<(String, String)>)> {
+    let mut parts = split_unquoted(value, ';');
+    let essence = parts.next()?.trim();
+    let params = parts
+        .filter(|part| !part.trim().is_empty())
+        .map(parse_param)
+        .collect::<Option<Vec<(String, String)>>>()?;
+    Some((essence, params))
+}
+
+fn parse_param(param: &str) -> Option<(String, String)> {
+    let (name, value) = param.trim().split_once('=')?;
+    let name = name.trim();
+    if !is_token(name) {
+        return None;
+    }
+    let value = value.trim();
+    let value = match value.strip_prefix('"') {
+        Some(quoted) => unquote(quoted.strip_suffix('"')?),
+        None if is_token(value) => value.to_string(),
+        None => return None,
+    };
+    Some((name.to_ascii_lowercase(), value))
+}
+
+fn unquote(value: &str) -> String {
+    let mut unquoted = String::new();
+    let mut characters = value.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => unquoted.extend(characters.next()),
+            character => unquoted.push(character),
+        }
+    }
+    unquoted
+}
+
+fn quote(value: &str) -> String {
+    if is_token(value) {
+        return value.to_string();
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{
+        Accept, AcceptEncoding, Authorization, CacheControl, ContentLength, ContentType, Date,
+        Host, QualityItem, Range, TypedHeader,
+    };
+    use crate::range::ByteRange;
+    use crate::types::{Header, HeaderMap};
+
+    fn round_trip<H: TypedHeader + PartialEq + std::fmt::Debug>(value: &str, expected: H) {
+        let parsed = H::parse(value).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(H::parse(&parsed.format()).unwrap(), expected);
+    }
+
+    #[test]
+    pub fn parse_and_format_headers() {
+        round_trip(
+            "Text/HTML; Charset=\"utf-8\"; title=\"a \\\"b\\\"\"",
+            ContentType::new("text/html")
+                .param("charset", "utf-8")
+                .param("title", "a \"b\""),
+        );
+        round_trip("42", ContentLength(42));
+        round_trip(
+            "bytes=0-4,-3",
+            Range(vec![ByteRange::FromTo(0, 4), ByteRange::Suffix(3)]),
+        );
+        round_trip(
+            "no-cache, max-age=60, private=\"Set-Cookie, X-Token\"",
+            CacheControl::new()
+                .directive("no-cache", None)
+                .directive("max-age", Some("60"))
+                .directive("private", Some("Set-Cookie, X-Token")),
+        );
+        round_trip("Example.com:8080", Host::new("example.com", Some(8080)));
+        round_trip("[::1]", Host::new("[::1]", None));
+        round_trip(
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+            Date(UNIX_EPOCH + Duration::from_secs(784111777)),
+        );
+
+        assert_eq!(ContentLength::parse("+1"), None);
+        assert_eq!(Host::parse("example.com:port"), None);
+        assert_eq!(Host::parse("[::1]garbage"), None);
+        assert_eq!(
+            Host::parse("example.com:"),
+            Some(Host::new("example.com", None))
+        );
+        assert_eq!(Host::parse("[::1]:"), Some(Host::new("[::1]", None)));
+        assert_eq!(ContentType::parse("text"), None);
+    }
+
+    #[test]
+    pub fn quality_values() {
+        let accept = Accept::parse("text/*;q=0.3, text/html;level=1, */*;q=0.05").unwrap();
+        assert_eq!(accept.0[1], QualityItem::new("text/html;level=1", 1000));
+        assert_eq!(accept.quality("text/plain"), 300);
+        assert_eq!(accept.quality("image/png"), 50);
+        assert_eq!(accept.quality("text/html;level=1"), 1000);
+        assert_eq!(accept.quality("text/html"), 300);
+        assert_eq!(accept.preferred()[0].value, "text/html;level=1");
+        assert_eq!(
+            accept.format(),
+            "text/*;q=0.3, text/html;level=1, */*;q=0.05"
+        );
+        let accept = Accept::parse("text/html;q=0.5, */*;q=0.8, application/json;q=0").unwrap();
+        assert_eq!(accept.quality("text/html"), 500);
+        assert_eq!(accept.quality("application/json"), 0);
+        assert_eq!(accept.quality("image/png"), 800);
+
+        let encoding = AcceptEncoding::parse("gzip;q=1.0, br;q=0, *;q=0.5").unwrap();
+        assert_eq!(encoding.quality("br"), 0);
+        assert_eq!(encoding.quality("deflate"), 500);
+        assert_eq!(encoding.preferred().len(), 2);
+        assert_eq!(encoding.format(), "gzip, br;q=0, *;q=0.5");
+        assert_eq!(Accept::parse("text/html;q=1.5"), None);
+    }
+
+    #[test]
+    pub fn authorization_and_header_map() {
+        round_trip(
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ==",
+            Authorization::basic("Aladdin", "open sesame"),
+        );
+        assert_eq!(Authorization::basic("a", "b").format(), "Basic YTpi");
+        round_trip("Bearer abc.def", Authorization::bearer("abc.def"));
+
+        let mut headers = HeaderMap::new();
+        headers.append(Header::ACCEPT_ENCODING, "gzip");
+        headers.append("accept-encoding", "br;q=0.5");
+        let encoding: AcceptEncoding = headers.typed().unwrap();
+        assert_eq!(encoding.quality("br"), 500);
+        headers.insert_typed(&ContentLength(7));
+        assert_eq!(headers.get("content-length"), Some("7"));
+        assert_eq!(headers.typed::<Host>(), None);
+    }
+}
@@ -1,18 +1,54 @@
 extern crate bincode;
 
 use std::error::Error;
-use std::io::{ErrorKind, Read, Write};
+use std::io::{ErrorKind, Write};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener, TcpStream};
 use std::sync::{mpsc, Arc, Mutex};
 use std::thread::JoinHandle;
 use std::time::Duration;
 
-use crate::types::{HttpVersion, Request, Response, ResponseCode};
+use crate::connection::{is_persistent, Connection, ReadError, Timeouts};
+use crate::handler::Handler;
+use crate::middleware::{Middleware, Stack};
+use crate::parser::{Limits, ParseError, ParseMode};
+use crate::types::{Header, HttpVersion, Method, Response};
+use crate::virtual_host::validate_host;
 
 use super::thread_pool::ThreadPool;
 
+// KeepAlive
+#[derive(Debug, Clone)]
+pub struct KeepAlive {
+    pub enabled: bool,
+    pub max_requests: usize,
+}
+impl Default for KeepAlive {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_requests: 100,
+        }
+    }
+}
+
+// ErrorPage
+pub type ErrorPage = dyn Fn(&ParseError) -> Response + Send + Sync;
+
+// Config
+#[derive(Clone, Default)]
+struct Config {
+    keep_alive: KeepAlive,
+    limits: Limits,
+    mode: ParseMode,
+    timeouts: Timeouts,
+    error_page: Option<Arc<ErrorPage>>,
+}
+
 pub struct Server {
     port: u16,
+    handler: Arc<dyn Handler>,
+    middleware: Vec<Box<dyn Middleware>>,
+    config: Config,
     listen_thread: Option<JoinHandle<()>>,
     cli_thread: Option<JoinHandle<()>>,
     state: State,
@@ -23,34 +59,80 @@ enum State {
 }
 
 impl Server {
-    pub fn new(port: u16) -> Server {
+    pub fn new<H: Handler>(port: u16, handler: H) -> Server {
         Server {
             port,
+            handler: Arc::new(handler),
+            middleware: Vec::new(),
+            config: Config::default(),
             listen_thread: None,
             cli_thread: None,
             state: State::Running,
         }
     }
 
+    pub fn middleware<M: Middleware>(&mut self, middleware: M) -> &mut Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    pub fn keep_alive(&mut self, keep_alive: KeepAlive) -> &mut Self {
+        self.config.keep_alive = keep_alive;
+        self
+    }
+
+    pub fn limits(&mut self, limits: Limits) -> &mut Self {
+        self.config.limits = limits;
+        self
+    }
+
+    pub fn parse_mode(&mut self, mode: ParseMode) -> &mut Self {
+        self.config.mode = mode;
+        self
+    }
+
+    pub fn timeouts(&mut self, timeouts: Timeouts) -> &mut Self {
+        self.config.timeouts = timeouts;
+        self
+    }
+
+    pub fn error_page<F>(&mut self, error_page: F) -> &mut Self
+    where
+        F: Fn(&ParseError) -> Response + Send + Sync + 'static,
+    {
+        self.config.error_page = Some(Arc::new(error_page));
+        self
+    }
+
     pub fn start(&mut self) -> Result<(), Box<dyn Error>> {
         let socket_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), self.port);
         let bind_result = TcpListener::bind(socket_address);
 
         match bind_result {
-            Ok(listener) => Ok(self.start_threads(listener)),
+            Ok(listener) => {
+                self.start_threads(listener);
+                Ok(())
+            }
             Err(e) => Err(Box::new(e)),
         }
     }
 
     fn start_threads(&mut self, listener: TcpListener) {
         println!("Starting server on port {:?}", self.port);
-        let port = self.port.clone();
+        let port = self.port;
+        let handler: Arc<dyn Handler> = Arc::new(Stack::from_parts(
+            std::mem::take(&mut self.middleware),
+            Arc::clone(&self.handler),
+        ));
+
+        let config = self.config.clone();
 
         let (main_sender, main_receiver) = mpsc::channel();
         let main_receiver = Arc::new(Mutex::new(main_receiver));
 
         let clone1 = Arc::clone(&main_receiver);
-        let listen_thread = std::thread::spawn(move || do_listen_work(port, &listener, clone1));
+        let listen_thread =
+            std::thread::spawn(move || do_listen_work(port, &listener, handler, config, clone1));
 
         self.listen_thread = Some(listen_thread);
 
@@ -83,30 +165,28 @@ impl Server {
 fn do_listen_work(
     port: u16,
     listener: &TcpListener,
+    handler: Arc<dyn Handler>,
+    config: Config,
     state_receiver: Arc<Mutex<mpsc::Receiver<State>>>,
 ) {
     listener.set_nonblocking(true).unwrap();
     let thread_pool = ThreadPool::new(8);
     println!("Server Started on {:?}", port);
     loop {
-        match state_receiver
+        if let Ok(State::Terminated) = state_receiver
             .lock()
             .unwrap()
             .recv_timeout(Duration::from_millis(100))
         {
-            Ok(state) => match state {
-                State::Terminated => {
-                    println!("Terminating connection worker");
-                    break;
-                }
-                _ => (),
-            },
-            _ => (),
+            println!("Terminating connection worker");
+            break;
         }
         let stream = listener.accept();
         match stream {
             Ok((stream, address)) => {
-                thread_pool.execute(move || handle_connection(address, stream));
+                let handler = Arc::clone(&handler);
+                let config = config.clone();
+                thread_pool.execute(move || handle_connection(address, stream, handler, config));
             }
 
             Err(ref e) if e.kind() == ErrorKind::WouldBlock => {
@@ -121,20 +201,14 @@ fn do_cli_work(sender: mpsc::Sender<State>, state_receiver: Arc<Mutex<mpsc::Rece
     println!("Starting cli worker");
     loop {
         let mut input = String::from("");
-        match state_receiver
+        if let Ok(State::Terminated) = state_receiver
             .lock()
             .unwrap()
             .recv_timeout(Duration::from_millis(100))
         {
-            Ok(state) => match state {
-                State::Terminated => {
-                    println!("Terminating Cli worker");
-                    break;
-                }
-                _ => (),
-            },
-            _ => (),
-        };
+            println!("Terminating Cli worker");
+            break;
+        }
         print!("> ");
         std::io::stdout().flush().unwrap();
         std::io::stdin().read_line(&mut input).unwrap();
@@ -147,50 +221,87 @@ fn do_cli_work(sender: mpsc::Sender<State>, state_receiver: Arc<Mutex<mpsc::Rece
     }
 }
 
-fn handle_connection(address: SocketAddr, mut stream: TcpStream) {
+fn handle_connection(
+    address: SocketAddr,
+    stream: TcpStream,
+    handler: Arc<dyn Handler>,
+    config: Config,
+) {
     println!("Connection received from: {address:?}");
-    let mut buffer = [0; 512];
-    match stream.read(&mut buffer) {
-        Ok(_) => {
-            match std::str::from_utf8(&buffer) {
-                Ok(buffer_string) => {
-                    let request = Request::parse_from_string(&buffer_string.to_string());
-                    match &request {
-                        Some(valid_request) => {
-                            let resource = &valid_request.request_line.resource;
-                            let mut response = Response::new(
-                                valid_request.request_line.version.clone(),
-                                ResponseCode::Ok,
-                                format!("{} found", resource).into_bytes(),
-                            );
-                            response.add_header("Content-type", "text/html");
-                            response.add_header(
-                                "Content-length",
-                                format!("{}", response.body_length()).as_str(),
-                            );
-                            stream.write(response.as_string().as_bytes()).unwrap();
-                        }
-                        None => {
-                            let response = Response::new(
-                                HttpVersion::HttpV1_1,
-                                ResponseCode::BadRequest,
-                                format!("invalid request").into_bytes(),
-                            );
-                            stream.write(response.as_string().as_bytes()).unwrap();
-                        }
-                    }
-                }
-                Err(_) => {
-                    let response = Response::new(
-                        HttpVersion::HttpV1_1,
-                        ResponseCode::BadRequest,
-                        format!("invalid request").into_bytes(),
-                    );
-                    stream.write(response.as_string().as_bytes()).unwrap();
-                }
-            };
+    let keep_alive = &config.keep_alive;
+    let mut connection = Connection::new(stream)
+        .limits(config.limits.clone())
+        .mode(config.mode)
+        .timeouts(config.timeouts.clone());
+    let mut served = 0;
+    loop {
+        let request = match connection.read_request() {
+            Ok(Some(request)) => request,
+            Ok(None) => break,
+            Err(ReadError::Invalid(error)) => {
+                write_error(&mut connection, &config, address, &error);
+                break;
+            }
+            Err(ReadError::Io(error)) => {
+                eprintln!("{:?}", error);
+                break;
+            }
+        };
+        if let Err(error) = validate_host(&request) {
+            write_error(&mut connection, &config, address, &error);
+            break;
         }
-        Err(error) => eprintln!("{:?}", error),
+        served += 1;
+        let version = request.request_line.version.clone();
+        let method = request.request_line.method.clone();
+        let mut persistent =
+            keep_alive.enabled && served < keep_alive.max_requests && is_persistent(&request);
+        let mut response = handler.handle(request);
+        response.set_version(HttpVersion::HttpV1_1);
+        if method == Method::Head {
+            response.strip_body(&version);
+        }
+        persistent &= !response
+            .header(Header::CONNECTION)
+            .is_some_and(|value| has_token(value, "close"));
+        persistent &= !(response.is_streaming()
+            && response.stream_length().is_none()
+            && version == HttpVersion::HttpV1_0);
+        match (persistent, &version) {
+            (true, HttpVersion::HttpV1_0) => response.set_header(Header::CONNECTION, "keep-alive"),
+            (true, _) => (),
+            (false, _) => response.set_header(Header::CONNECTION, "close"),
+        }
+        if let Err(error) = connection.write_response(response, &version) {
+            eprintln!("{:?}", error);
+            break;
+        }
+        if !persistent {
+            break;
+        }
+    }
+    let _ = connection.stream_mut().shutdown(std::net::Shutdown::Both);
+}
+
+fn write_error(
+    connection: &mut Connection<TcpStream>,
+    config: &Config,
+    address: SocketAddr,
+    error: &ParseError,
+) {
+    eprintln!("Invalid request from {address:?}: {error}");
+    let mut response = match &config.error_page {
+        Some(error_page) => error_page(error),
+        None => Response::from_code(HttpVersion::HttpV1_1, error.response_code()),
     };
-    stream.shutdown(std::net::Shutdown::Read).unwrap();
+    response.set_header(Header::CONNECTION, "close");
+    if let Err(error) = connection.write_response(response, &HttpVersion::HttpV1_1) {
+        eprintln!("{:?}", error);
+    }
+}
+
+fn has_token(value: &str, token: &str) -> bool {
+    value
+        .split(',')
+        .any(|item| item.trim().eq_ignore_ascii_case(token))
 }
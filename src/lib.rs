@@ -1,4 +1,17 @@
+pub mod chunked;
+pub mod conditional;
+pub mod connection;
+pub mod date;
+pub mod handler;
+pub mod headers;
 pub mod http;
+pub mod middleware;
+pub mod parser;
+pub mod range;
+pub mod router;
+pub mod static_files;
 pub mod thread_pool;
 mod traits;
 pub mod types;
+pub mod uri;
+pub mod virtual_host;
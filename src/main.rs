@@ -1,7 +1,16 @@
 use rust_http_server::http::Server;
+use rust_http_server::types::{Header, Request, Response, ResponseCode};
 
 fn main() {
-    let mut server = Server::new(50000);
+    let mut server = Server::new(50000, |request: Request| {
+        let mut response = Response::new(
+            request.request_line.version,
+            ResponseCode::Ok,
+            format!("{} found", request.request_line.resource).into_bytes(),
+        );
+        response.add_header(Header::CONTENT_TYPE, "text/html");
+        response
+    });
     let result = server.start();
     match result {
         Ok(_) => (),
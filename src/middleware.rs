@@ -0,0 +1,152 @@
+use std::sync::Arc;
+
+use crate::handler::Handler;
+use crate::types::{Request, Response};
+
+// Middleware
+pub trait Middleware: Send + Sync + 'static {
+    fn call(&self, request: Request, next: &Next) -> Response;
+}
+impl<F> Middleware for F
+where
+    F: Fn(Request, &Next) -> Response + Send + Sync + 'static,
+{
+    fn call(&self, request: Request, next: &Next) -> Response {
+        self(request, next)
+    }
+}
+
+// Next
+pub struct Next<'a> {
+    middleware: &'a [Box<dyn Middleware>],
+    handler: &'a dyn Handler,
+}
+impl<'a> Next<'a> {
+    pub fn new(middleware: &'a [Box<dyn Middleware>], handler: &'a dyn Handler) -> Self {
+        Self {
+            middleware,
+            handler,
+        }
+    }
+
+    pub fn run(&self, request: Request) -> Response {
+        match self.middleware.split_first() {
+            Some((first, rest)) => first.call(request, &Next::new(rest, self.handler)),
+            None => self.handler.handle(request),
+        }
+    }
+}
+
+// Before
+pub struct Before<F>(F);
+impl<F> Middleware for Before<F>
+where
+    F: Fn(Request) -> Result<Request, Response> + Send + Sync + 'static,
+{
+    fn call(&self, request: Request, next: &Next) -> Response {
+        match (self.0)(request) {
+            Ok(request) => next.run(request),
+            Err(response) => response,
+        }
+    }
+}
+pub fn before<F>(f: F) -> Before<F>
+where
+    F: Fn(Request) -> Result<Request, Response> + Send + Sync + 'static,
+{
+    Before(f)
+}
+
+// After
+pub struct After<F>(F);
+impl<F> Middleware for After<F>
+where
+    F: Fn(Response) -> Response + Send + Sync + 'static,
+{
+    fn call(&self, request: Request, next: &Next) -> Response {
+        (self.0)(next.run(request))
+    }
+}
+pub fn after<F>(f: F) -> After<F>
+where
+    F: Fn(Response) -> Response + Send + Sync + 'static,
+{
+    After(f)
+}
+
+// Stack
+pub struct Stack {
+    middleware: Vec<Box<dyn Middleware>>,
+    handler: Arc<dyn Handler>,
+}
+impl Stack {
+    pub fn new<H: Handler>(handler: H) -> Self {
+        Self::from_parts(Vec::new(), Arc::new(handler))
+    }
+    pub(crate) fn from_parts(
+        middleware: Vec<Box<dyn Middleware>>,
+        handler: Arc<dyn Handler>,
+    ) -> Self {
+        Self {
+            middleware,
+            handler,
+        }
+    }
+
+    pub fn with<M: Middleware>(mut self, middleware: M) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+}
+impl Handler for Stack {
+    fn handle(&self, request: Request) -> Response {
+        Next::new(&self.middleware, self.handler.as_ref()).run(request)
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::{after, before, Next, Stack};
+    use crate::handler::Handler;
+    use crate::types::{HttpVersion, Request, Response, ResponseCode};
+
+    fn ok(request: Request) -> Response {
+        Response::new(request.request_line.version, ResponseCode::Ok, request.body)
+    }
+
+    #[test]
+    pub fn stack_runs_in_order() {
+        let stack = Stack::new(ok)
+            .with(|mut request: Request, next: &Next| {
+                request.body.extend_from_slice(b"a");
+                next.run(request)
+            })
+            .with(before(|mut request: Request| {
+                request.body.extend_from_slice(b"b");
+                Ok(request)
+            }))
+            .with(after(|mut response: Response| {
+                response.add_header("X-After", "1");
+                response
+            }));
+
+        let response = stack.handle(Request::parse_from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+        assert_eq!(response.body(), b"ab");
+        assert!(response.has_header("X-After"));
+    }
+
+    #[test]
+    pub fn before_short_circuits() {
+        let stack = Stack::new(ok).with(before(|_: Request| {
+            Err(Response::new(
+                HttpVersion::HttpV1_1,
+                ResponseCode::Unauthorized,
+                Vec::new(),
+            ))
+        }));
+
+        let response = stack.handle(Request::parse_from_str("GET / HTTP/1.1\r\n\r\n").unwrap());
+        assert_eq!(response.response_code(), &ResponseCode::Unauthorized);
+    }
+}
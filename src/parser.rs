@@ -0,0 +1,541 @@
+use crate::chunked::ChunkedDecoder;
+use std::fmt::{self, Display};
+
+use crate::types::{is_token, Header, HttpVersion, Request, RequestLine, ResponseCode};
+
+// Status
+#[derive(Debug)]
+pub enum Status {
+    Incomplete,
+    Complete(Request),
+}
+
+// State
+#[derive(Debug)]
+enum State {
+    RequestLine,
+    Headers(Request),
+    Body(Request, usize),
+    Chunked(Request, ChunkedDecoder),
+}
+
+// Framing
+enum Framing {
+    Length(usize),
+    Chunked,
+}
+
+// Limits
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub max_request_line: usize,
+    pub max_header_bytes: usize,
+    pub max_headers: usize,
+    pub max_body: usize,
+}
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_request_line: 8 * 1024,
+            max_header_bytes: 64 * 1024,
+            max_headers: 100,
+            max_body: 10 * 1024 * 1024,
+        }
+    }
+}
+
+// ParseError
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    Incomplete,
+    InvalidLineEnding,
+    MalformedRequestLine,
+    InvalidMethod(String),
+    InvalidTarget(String),
+    InvalidVersion(String),
+    UnsupportedVersion(String),
+    MalformedHeader,
+    RequestLineTooLong,
+    HeadersTooLarge,
+    BodyTooLarge,
+    InvalidContentLength,
+    AmbiguousFraming,
+    UnsupportedTransferCoding(String),
+    MalformedChunk,
+    MissingHost,
+    InvalidHost(String),
+    Timeout,
+}
+impl ParseError {
+    pub fn response_code(&self) -> ResponseCode {
+        match self {
+            Self::UnsupportedTransferCoding(_) => ResponseCode::NotImplemented,
+            Self::UnsupportedVersion(_) => ResponseCode::HttpVersionNotSupported,
+            Self::RequestLineTooLong => ResponseCode::UriTooLong,
+            Self::HeadersTooLarge => ResponseCode::RequestHeaderFieldsTooLarge,
+            Self::BodyTooLarge => ResponseCode::ContentTooLarge,
+            Self::Timeout => ResponseCode::RequestTimeout,
+            _ => ResponseCode::BadRequest,
+        }
+    }
+}
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Incomplete => write!(f, "request ended before it was complete"),
+            Self::InvalidLineEnding => write!(f, "line not terminated by CRLF"),
+            Self::MalformedRequestLine => write!(f, "malformed request line"),
+            Self::InvalidMethod(method) => write!(f, "invalid method {method:?}"),
+            Self::InvalidTarget(target) => write!(f, "invalid request target {target:?}"),
+            Self::InvalidVersion(version) => write!(f, "invalid HTTP version {version:?}"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported HTTP version {version:?}"),
+            Self::MalformedHeader => write!(f, "malformed header field"),
+            Self::RequestLineTooLong => write!(f, "request line too long"),
+            Self::HeadersTooLarge => write!(f, "header section too large"),
+            Self::BodyTooLarge => write!(f, "request body too large"),
+            Self::InvalidContentLength => write!(f, "invalid Content-Length"),
+            Self::AmbiguousFraming => write!(f, "ambiguous message framing"),
+            Self::UnsupportedTransferCoding(coding) => {
+                write!(f, "unsupported transfer coding {coding:?}")
+            }
+            Self::MalformedChunk => write!(f, "malformed chunked body"),
+            Self::MissingHost => write!(f, "missing Host header"),
+            Self::InvalidHost(host) => write!(f, "invalid Host header {host:?}"),
+            Self::Timeout => write!(f, "request not received in time"),
+        }
+    }
+}
+impl std::error::Error for ParseError {}
+
+// ParseMode
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ParseMode {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+// Parser
+#[derive(Debug)]
+pub struct Parser {
+    state: State,
+    limits: Limits,
+    mode: ParseMode,
+    header_bytes: usize,
+}
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Parser {
+    pub fn new() -> Self {
+        Self::with_limits(Limits::default())
+    }
+    pub fn with_limits(limits: Limits) -> Self {
+        Self {
+            state: State::RequestLine,
+            limits,
+            mode: ParseMode::default(),
+            header_bytes: 0,
+        }
+    }
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, State::RequestLine)
+    }
+    pub fn in_body(&self) -> bool {
+        matches!(self.state, State::Body(..) | State::Chunked(..))
+    }
+
+    pub fn parse(&mut self, buffer: &[u8]) -> Result<(usize, Status), ParseError> {
+        let mut consumed = 0;
+        loop {
+            let remaining = &buffer[consumed..];
+            let state = std::mem::replace(&mut self.state, State::RequestLine);
+            self.state = match state {
+                State::RequestLine => {
+                    let line = next_line(remaining, self.mode)?;
+                    let length = line.map_or(remaining.len(), |(line, _)| line.len());
+                    if length > self.limits.max_request_line {
+                        return Err(ParseError::RequestLineTooLong);
+                    }
+                    let Some((line, size)) = line else {
+                        return Ok((consumed, Status::Incomplete));
+                    };
+                    consumed += size;
+                    if line.is_empty() {
+                        continue;
+                    }
+                    self.header_bytes = 0;
+                    State::Headers(Request::new(parse_request_line(line, self.mode)?))
+                }
+                State::Headers(mut request) => {
+                    let line = next_line(remaining, self.mode)?;
+                    let length = line.map_or(remaining.len(), |(_, size)| size);
+                    if self.header_bytes + length > self.limits.max_header_bytes {
+                        return Err(ParseError::HeadersTooLarge);
+                    }
+                    let Some((line, size)) = line else {
+                        self.state = State::Headers(request);
+                        return Ok((consumed, Status::Incomplete));
+                    };
+                    consumed += size;
+                    self.header_bytes += size;
+                    if let Some(b' ' | b'\t') = line.first() {
+                        let continuation = parse_value(line)?;
+                        if self.mode == ParseMode::Strict
+                            || !request.headers.fold_last(&continuation)
+                        {
+                            return Err(ParseError::MalformedHeader);
+                        }
+                        self.state = State::Headers(request);
+                        continue;
+                    }
+                    if !line.is_empty() {
+                        if request.headers.len() == self.limits.max_headers {
+                            return Err(ParseError::HeadersTooLarge);
+                        }
+                        request.headers.push(parse_header(line, self.mode)?);
+                        self.state = State::Headers(request);
+                        continue;
+                    }
+                    match framing(&mut request, self.mode)? {
+                        Framing::Length(length) if length > self.limits.max_body => {
+                            return Err(ParseError::BodyTooLarge)
+                        }
+                        Framing::Length(0) => return Ok((consumed, Status::Complete(request))),
+                        Framing::Length(length) => State::Body(request, length),
+                        Framing::Chunked => {
+                            let decoder = ChunkedDecoder::with_limits(
+                                self.limits.max_headers - request.headers.len(),
+                                self.limits.max_header_bytes - self.header_bytes,
+                            );
+                            State::Chunked(request, decoder)
+                        }
+                    }
+                }
+                State::Body(mut request, length) => {
+                    let take = (length - request.body.len()).min(remaining.len());
+                    request.body.extend_from_slice(&remaining[..take]);
+                    consumed += take;
+                    if request.body.len() == length {
+                        return Ok((consumed, Status::Complete(request)));
+                    }
+                    self.state = State::Body(request, length);
+                    return Ok((consumed, Status::Incomplete));
+                }
+                State::Chunked(mut request, mut decoder) => {
+                    consumed += decoder.decode(remaining)?;
+                    if decoder.body_length() > self.limits.max_body {
+                        return Err(ParseError::BodyTooLarge);
+                    }
+                    if !decoder.is_done() {
+                        self.state = State::Chunked(request, decoder);
+                        return Ok((consumed, Status::Incomplete));
+                    }
+                    let (body, trailers) = decoder.finish();
+                    request.body = body;
+                    request.trailers.extend(trailers);
+                    return Ok((consumed, Status::Complete(request)));
+                }
+            };
+        }
+    }
+}
+
+fn next_line(buffer: &[u8], mode: ParseMode) -> Result<Option<(&[u8], usize)>, ParseError> {
+    let Some(index) = buffer.iter().position(|byte| *byte == b'\n') else {
+        return Ok(None);
+    };
+    match index.checked_sub(1).map(|before| buffer[before]) {
+        Some(b'\r') => Ok(Some((&buffer[..index - 1], index + 1))),
+        _ if mode == ParseMode::Lenient => Ok(Some((&buffer[..index], index + 1))),
+        _ => Err(ParseError::InvalidLineEnding),
+    }
+}
+
+fn parse_request_line(line: &[u8], mode: ParseMode) -> Result<RequestLine, ParseError> {
+    if !line
+        .iter()
+        .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+    {
+        return Err(ParseError::MalformedRequestLine);
+    }
+    if mode == ParseMode::Strict && line.iter().filter(|byte| **byte == b' ').count() != 2 {
+        return Err(ParseError::MalformedRequestLine);
+    }
+    let line = std::str::from_utf8(line).map_err(|_| ParseError::MalformedRequestLine)?;
+    RequestLine::from_string(line)
+}
+
+fn parse_header(line: &[u8], mode: ParseMode) -> Result<Header, ParseError> {
+    let colon = line
+        .iter()
+        .position(|byte| *byte == b':')
+        .ok_or(ParseError::MalformedHeader)?;
+    let field_name =
+        std::str::from_utf8(&line[..colon]).map_err(|_| ParseError::MalformedHeader)?;
+    let field_name = match mode {
+        ParseMode::Strict => field_name,
+        ParseMode::Lenient => field_name.trim(),
+    };
+    if !is_token(field_name) {
+        return Err(ParseError::MalformedHeader);
+    }
+    Ok(Header::new(field_name, &parse_value(&line[colon + 1..])?))
+}
+
+fn parse_value(bytes: &[u8]) -> Result<String, ParseError> {
+    if bytes
+        .iter()
+        .any(|byte| matches!(byte, b'\r' | b'\n' | b'\0'))
+    {
+        return Err(ParseError::MalformedHeader);
+    }
+    let field_value: String = bytes.iter().map(|byte| *byte as char).collect();
+    Ok(field_value.trim_matches([' ', '\t']).to_string())
+}
+
+fn framing(request: &mut Request, mode: ParseMode) -> Result<Framing, ParseError> {
+    let codings: Vec<String> = request
+        .headers
+        .get_all(Header::TRANSFER_ENCODING)
+        .flat_map(|value| value.split(','))
+        .map(|coding| coding.trim().to_ascii_lowercase())
+        .filter(|coding| !coding.is_empty())
+        .collect();
+    if codings.is_empty() {
+        return Ok(Framing::Length(content_length(request, mode)?));
+    }
+    if request.headers.contains_key(Header::CONTENT_LENGTH) {
+        if mode == ParseMode::Strict {
+            return Err(ParseError::AmbiguousFraming);
+        }
+        request.headers.remove(Header::CONTENT_LENGTH);
+        request.close = true;
+    }
+    let chunked_count = codings.iter().filter(|coding| *coding == "chunked").count();
+    if request.request_line.version == HttpVersion::HttpV1_0
+        || codings.last().map(String::as_str) != Some("chunked")
+        || chunked_count > 1
+    {
+        return Err(ParseError::AmbiguousFraming);
+    }
+    if codings.len() > 1 {
+        return Err(ParseError::UnsupportedTransferCoding(codings[0].clone()));
+    }
+    Ok(Framing::Chunked)
+}
+
+fn content_length(request: &Request, mode: ParseMode) -> Result<usize, ParseError> {
+    let mut length = None;
+    for value in request.headers.get_all(Header::CONTENT_LENGTH) {
+        if mode == ParseMode::Strict && length.is_some() {
+            return Err(ParseError::InvalidContentLength);
+        }
+        let value = value.trim();
+        if value.is_empty() || !value.bytes().all(|byte| byte.is_ascii_digit()) {
+            return Err(ParseError::InvalidContentLength);
+        }
+        let value: usize = value
+            .parse()
+            .map_err(|_| ParseError::InvalidContentLength)?;
+        if length.is_some_and(|length| length != value) {
+            return Err(ParseError::InvalidContentLength);
+        }
+        length = Some(value);
+    }
+    Ok(length.unwrap_or(0))
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::{Limits, ParseError, ParseMode, Parser, Status};
+    use crate::types::{Method, Request, ResponseCode};
+
+    #[test]
+    pub fn parse_byte_by_byte() {
+        let bytes = b"POST /items HTTP/1.1\r\nContent-Length: 5\r\n\r\nhelloGET";
+        let mut parser = Parser::new();
+        let mut buffer = Vec::new();
+        let mut request = None;
+        for byte in bytes {
+            buffer.push(*byte);
+            let (consumed, status) = parser.parse(&buffer).unwrap();
+            buffer.drain(..consumed);
+            if let Status::Complete(complete) = status {
+                request = Some(complete);
+                break;
+            }
+        }
+        let request = request.unwrap();
+        assert_eq!(request.request_line.method, Method::Post);
+        assert_eq!(request.body, b"hello");
+        assert!(parser.is_idle());
+
+        let (consumed, status) = parser.parse(&bytes[bytes.len() - 3..]).unwrap();
+        assert_eq!(consumed, 0);
+        assert!(matches!(status, Status::Incomplete));
+    }
+
+    #[test]
+    pub fn parse_large_and_non_utf8_requests() {
+        let mut bytes = b"\r\nGET /large HTTP/1.1\r\nX-Latin: caf\xe9\r\n".to_vec();
+        for index in 0..64 {
+            bytes.extend_from_slice(format!("X-Header-{index}: {}\r\n", "v".repeat(32)).as_bytes());
+        }
+        bytes.extend_from_slice(b"\r\nGET /next HTTP/1.1\r\n\r\n");
+        let mut parser = Parser::new();
+        let (consumed, status) = parser.parse(&bytes).unwrap();
+        let Status::Complete(request) = status else {
+            panic!("expected a complete request");
+        };
+        assert_eq!(request.headers.len(), 65);
+        assert_eq!(request.header("X-Latin"), Some("caf\u{e9}"));
+        assert_eq!(&bytes[consumed..], b"GET /next HTTP/1.1\r\n\r\n");
+    }
+
+    #[test]
+    pub fn reject_malformed_lines() {
+        for bytes in [
+            &b"GET /\xff HTTP/1.1\r\n\r\n"[..],
+            b"GET / HTTP/1.1\r\nno colon\r\n\r\n",
+            b"GET / HTTP/1.1\r\n: empty\r\n\r\n",
+        ] {
+            assert_eq!(
+                Parser::new().parse(bytes).unwrap_err().response_code(),
+                ResponseCode::BadRequest
+            );
+        }
+    }
+
+    #[test]
+    pub fn strict_and_lenient_modes() {
+        for bytes in [
+            &b"GET / HTTP/1.1\r\nHost : a\r\n\r\n"[..],
+            b"GET / HTTP/1.1\r\nX-Folded: a\r\n b\r\n\r\n",
+            b"GET / HTTP/1.1\nHost: a\n\n",
+            b"GET  / HTTP/1.1\r\n\r\n",
+            b"POST / HTTP/1.1\r\nContent-Length: 1\r\nContent-Length: 1\r\n\r\na",
+            b"POST / HTTP/1.1\r\nContent-Length: 1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        ] {
+            assert_eq!(
+                Parser::new().parse(bytes).unwrap_err().response_code(),
+                ResponseCode::BadRequest
+            );
+            let mut parser = Parser::new().mode(ParseMode::Lenient);
+            let (consumed, status) = parser.parse(bytes).unwrap();
+            assert_eq!(consumed, bytes.len());
+            assert!(matches!(status, Status::Complete(_)));
+        }
+
+        let bytes = b"POST / HTTP/1.1\r\nX-Folded: a\r\n\tb\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n1\r\nz\r\n0\r\n\r\n";
+        let mut parser = Parser::new().mode(ParseMode::Lenient);
+        let Status::Complete(request) = parser.parse(bytes).unwrap().1 else {
+            panic!("expected a complete request");
+        };
+        assert_eq!(request.header("X-Folded"), Some("a b"));
+        assert_eq!(request.header("Content-Length"), None);
+        assert_eq!(request.body, b"z");
+        assert!(request.close);
+
+        let bytes = b"GET / HTTP/1.1\r\nX-Bad: a\rb\r\n\r\n";
+        let mut parser = Parser::new().mode(ParseMode::Lenient);
+        assert_eq!(
+            parser.parse(bytes).unwrap_err().response_code(),
+            ResponseCode::BadRequest
+        );
+    }
+
+    #[test]
+    pub fn typed_parse_errors() {
+        for (bytes, error, response_code) in [
+            (
+                &b"GE(T / HTTP/1.1\r\n\r\n"[..],
+                ParseError::InvalidMethod(String::from("GE(T")),
+                ResponseCode::BadRequest,
+            ),
+            (
+                b"GET index.html HTTP/1.1\r\n\r\n",
+                ParseError::InvalidTarget(String::from("index.html")),
+                ResponseCode::BadRequest,
+            ),
+            (
+                b"GET / HTTP/9.9\r\n\r\n",
+                ParseError::UnsupportedVersion(String::from("HTTP/9.9")),
+                ResponseCode::HttpVersionNotSupported,
+            ),
+            (
+                b"GET / HTTQ/1.1\r\n\r\n",
+                ParseError::InvalidVersion(String::from("HTTQ/1.1")),
+                ResponseCode::BadRequest,
+            ),
+            (
+                b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip, chunked\r\n\r\n",
+                ParseError::UnsupportedTransferCoding(String::from("gzip")),
+                ResponseCode::NotImplemented,
+            ),
+        ] {
+            let parsed = Parser::new().parse(bytes).unwrap_err();
+            assert_eq!(parsed, error);
+            assert_eq!(parsed.response_code(), response_code);
+        }
+        assert_eq!(
+            Request::parse_from_string("GET / HTTP/1.1\r\nHost: a\r\n").unwrap_err(),
+            ParseError::Incomplete
+        );
+    }
+
+    #[test]
+    pub fn enforce_limits() {
+        let limits = Limits {
+            max_request_line: 32,
+            max_header_bytes: 64,
+            max_headers: 2,
+            max_body: 8,
+        };
+        for (bytes, response_code) in [
+            (
+                format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(32)),
+                ResponseCode::UriTooLong,
+            ),
+            (format!("GET /{}", "a".repeat(40)), ResponseCode::UriTooLong),
+            (
+                format!("GET / HTTP/1.1\r\nX-Long: {}\r\n\r\n", "a".repeat(64)),
+                ResponseCode::RequestHeaderFieldsTooLarge,
+            ),
+            (
+                String::from("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n"),
+                ResponseCode::RequestHeaderFieldsTooLarge,
+            ),
+            (
+                String::from("POST / HTTP/1.1\r\nContent-Length: 9\r\n\r\n"),
+                ResponseCode::ContentTooLarge,
+            ),
+            (
+                String::from("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n9\r\n123456789"),
+                ResponseCode::ContentTooLarge,
+            ),
+            (
+                String::from("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\nA: 1\r\nB: 2\r\n\r\n"),
+                ResponseCode::RequestHeaderFieldsTooLarge,
+            ),
+        ] {
+            let mut parser = Parser::with_limits(limits.clone());
+            assert_eq!(
+                parser.parse(bytes.as_bytes()).unwrap_err().response_code(),
+                response_code
+            );
+        }
+    }
+}
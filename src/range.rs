@@ -0,0 +1,326 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::middleware::{Middleware, Next};
+use crate::types::{BodyStream, Header, Method, Request, Response, ResponseCode};
+
+const MAX_RANGES: usize = 16;
+
+// ByteRange
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteRange {
+    FromTo(u64, u64),
+    From(u64),
+    Suffix(u64),
+}
+impl ByteRange {
+    pub fn parse(header: &str) -> Option<Vec<Self>> {
+        let (unit, ranges) = header.trim().split_once('=')?;
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return None;
+        }
+        let mut parsed = Vec::new();
+        for range in ranges.split(',').map(str::trim) {
+            if range.is_empty() {
+                continue;
+            }
+            let (start, end) = range.split_once('-')?;
+            let range = match (start.trim(), end.trim()) {
+                ("", suffix) => Self::Suffix(suffix.parse().ok()?),
+                (start, "") => Self::From(start.parse().ok()?),
+                (start, end) => {
+                    let (start, end) = (start.parse().ok()?, end.parse().ok()?);
+                    if end < start {
+                        return None;
+                    }
+                    Self::FromTo(start, end)
+                }
+            };
+            parsed.push(range);
+        }
+        if parsed.is_empty() {
+            return None;
+        }
+        Some(parsed)
+    }
+
+    pub fn resolve(&self, length: u64) -> Option<(u64, u64)> {
+        let (start, end) = match *self {
+            Self::FromTo(start, end) => (start, end.min(length.saturating_sub(1))),
+            Self::From(start) => (start, length.saturating_sub(1)),
+            Self::Suffix(0) => return None,
+            Self::Suffix(suffix) => (length.saturating_sub(suffix), length.saturating_sub(1)),
+        };
+        if start >= length {
+            return None;
+        }
+        Some((start, end))
+    }
+}
+impl std::fmt::Display for ByteRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::FromTo(start, end) => write!(f, "{start}-{end}"),
+            Self::From(start) => write!(f, "{start}-"),
+            Self::Suffix(suffix) => write!(f, "-{suffix}"),
+        }
+    }
+}
+
+pub fn apply_range(range: Option<&str>, mut response: Response) -> Response {
+    if response.response_code() != &ResponseCode::Ok
+        || (response.is_streaming() && !response.is_seekable())
+    {
+        return response;
+    }
+    response.set_header(Header::ACCEPT_RANGES, "bytes");
+    let Some(ranges) = range.and_then(ByteRange::parse) else {
+        return response;
+    };
+    let length = response
+        .stream_length()
+        .unwrap_or(response.body_length() as u64);
+    let Some(resolved) = select(&ranges, length) else {
+        return response;
+    };
+    let segments = match resolved.as_slice() {
+        [] => {
+            response.set_response_code(ResponseCode::RangeNotSatisfiable);
+            response.set_header(Header::CONTENT_RANGE, &format!("bytes */{length}"));
+            response.set_body(Vec::new());
+            return response;
+        }
+        [(start, end)] => {
+            response.set_header(
+                Header::CONTENT_RANGE,
+                &format!("bytes {start}-{end}/{length}"),
+            );
+            vec![Segment::Range(*start, *end)]
+        }
+        parts => {
+            let boundary = boundary();
+            let content_type = response.header(Header::CONTENT_TYPE).map(String::from);
+            let mut segments = Vec::new();
+            for (start, end) in parts {
+                let mut head = format!("\r\n--{boundary}\r\n");
+                if let Some(content_type) = &content_type {
+                    head += &format!("{}: {content_type}\r\n", Header::CONTENT_TYPE);
+                }
+                head += &format!(
+                    "{}: bytes {start}-{end}/{length}\r\n\r\n",
+                    Header::CONTENT_RANGE
+                );
+                segments.push(Segment::Bytes(head.into_bytes()));
+                segments.push(Segment::Range(*start, *end));
+            }
+            segments.push(Segment::Bytes(
+                format!("\r\n--{boundary}--\r\n").into_bytes(),
+            ));
+            response.set_header(
+                Header::CONTENT_TYPE,
+                &format!("multipart/byteranges; boundary={boundary}"),
+            );
+            segments
+        }
+    };
+    response.set_response_code(ResponseCode::PartialContent);
+    let file = response
+        .take_stream()
+        .and_then(|mut stream| stream.take_file());
+    match file {
+        Some(file) => {
+            let length = segments.iter().map(Segment::len).sum();
+            let sections = Sections {
+                file,
+                segments: segments.into(),
+            };
+            response.set_stream(BodyStream::from_reader(sections).sized(length));
+        }
+        None => {
+            let mut body = Vec::new();
+            for segment in &segments {
+                match segment {
+                    Segment::Bytes(bytes) => body.extend_from_slice(bytes),
+                    Segment::Range(start, end) => {
+                        body.extend_from_slice(&response.body()[*start as usize..=*end as usize])
+                    }
+                }
+            }
+            response.set_body(body);
+        }
+    }
+    response
+}
+
+// Segment
+enum Segment {
+    Bytes(Vec<u8>),
+    Range(u64, u64),
+}
+impl Segment {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Bytes(bytes) => bytes.len() as u64,
+            Self::Range(start, end) => (end + 1).saturating_sub(*start),
+        }
+    }
+}
+
+// Sections
+struct Sections {
+    file: File,
+    segments: VecDeque<Segment>,
+}
+impl Read for Sections {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(segment) = self.segments.front_mut() else {
+            return Ok(0);
+        };
+        let size = match segment {
+            Segment::Bytes(bytes) => {
+                let size = bytes.len().min(buf.len());
+                buf[..size].copy_from_slice(&bytes[..size]);
+                bytes.drain(..size);
+                size
+            }
+            Segment::Range(start, end) => {
+                let limit = (*end + 1 - *start).min(buf.len() as u64) as usize;
+                self.file.seek(SeekFrom::Start(*start))?;
+                let size = self.file.read(&mut buf[..limit])?;
+                if size == 0 {
+                    return Err(ErrorKind::UnexpectedEof.into());
+                }
+                *start += size as u64;
+                size
+            }
+        };
+        if segment.len() == 0 {
+            self.segments.pop_front();
+        }
+        Ok(size)
+    }
+}
+
+fn select(ranges: &[ByteRange], length: u64) -> Option<Vec<(u64, u64)>> {
+    if ranges.len() > MAX_RANGES {
+        return None;
+    }
+    let mut resolved: Vec<(u64, u64)> = ranges
+        .iter()
+        .filter_map(|range| range.resolve(length))
+        .collect();
+    let total: u64 = resolved.iter().map(|(start, end)| end - start + 1).sum();
+    if total > length {
+        return None;
+    }
+    resolved.sort_unstable();
+    let mut coalesced: Vec<(u64, u64)> = Vec::with_capacity(resolved.len());
+    for (start, end) in resolved {
+        match coalesced.last_mut() {
+            Some((_, last)) if start <= *last + 1 => *last = end.max(*last),
+            _ => coalesced.push((start, end)),
+        }
+    }
+    Some(coalesced)
+}
+
+fn boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos())
+        .unwrap_or_default();
+    format!("byteranges_{nanos:x}")
+}
+
+// Ranges
+pub struct Ranges;
+impl Middleware for Ranges {
+    fn call(&self, request: Request, next: &Next) -> Response {
+        if request.request_line.method != Method::Get {
+            return next.run(request);
+        }
+        let range = request.header(Header::RANGE).map(String::from);
+        apply_range(range.as_deref(), next.run(request))
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::{apply_range, ByteRange};
+    use crate::types::{HttpVersion, Response, ResponseCode};
+
+    fn response() -> Response {
+        let mut response = Response::new(
+            HttpVersion::HttpV1_1,
+            ResponseCode::Ok,
+            b"0123456789".to_vec(),
+        );
+        response.add_header("Content-type", "text/plain");
+        response
+    }
+
+    #[test]
+    pub fn parse_ranges() {
+        assert_eq!(
+            ByteRange::parse("bytes=0-4, 6-, -3"),
+            Some(vec![
+                ByteRange::FromTo(0, 4),
+                ByteRange::From(6),
+                ByteRange::Suffix(3)
+            ])
+        );
+        assert_eq!(ByteRange::parse("bytes=5-1"), None);
+        assert_eq!(ByteRange::parse("items=0-1"), None);
+    }
+
+    #[test]
+    pub fn single_and_suffix_ranges() {
+        let partial = apply_range(Some("bytes=2-4"), response());
+        assert_eq!(partial.response_code(), &ResponseCode::PartialContent);
+        assert_eq!(partial.body(), b"234");
+        assert_eq!(partial.header("Content-Range"), Some("bytes 2-4/10"));
+
+        let suffix = apply_range(Some("bytes=-3"), response());
+        assert_eq!(suffix.body(), b"789");
+
+        let whole = apply_range(None, response());
+        assert_eq!(whole.response_code(), &ResponseCode::Ok);
+        assert_eq!(whole.header("Accept-Ranges"), Some("bytes"));
+    }
+
+    #[test]
+    pub fn multiple_and_unsatisfiable_ranges() {
+        let multi = apply_range(Some("bytes=0-1,8-"), response());
+        assert_eq!(multi.response_code(), &ResponseCode::PartialContent);
+        let content_type = multi.header("Content-type").unwrap();
+        assert!(content_type.starts_with("multipart/byteranges; boundary="));
+        let body = String::from_utf8(multi.body().to_vec()).unwrap();
+        assert!(body.contains("Content-Range: bytes 0-1/10\r\n\r\n01"));
+        assert!(body.contains("Content-Range: bytes 8-9/10\r\n\r\n89"));
+
+        let unsatisfiable = apply_range(Some("bytes=20-30"), response());
+        assert_eq!(
+            unsatisfiable.response_code(),
+            &ResponseCode::RangeNotSatisfiable
+        );
+        assert_eq!(unsatisfiable.header("Content-Range"), Some("bytes */10"));
+    }
+
+    #[test]
+    pub fn coalesce_and_bound_ranges() {
+        let coalesced = apply_range(Some("bytes=4-5,0-1,2-3"), response());
+        assert_eq!(coalesced.body(), b"012345");
+        assert_eq!(coalesced.header("Content-Range"), Some("bytes 0-5/10"));
+
+        let overlapping = apply_range(Some("bytes=0-,0-"), response());
+        assert_eq!(overlapping.response_code(), &ResponseCode::Ok);
+        assert_eq!(overlapping.body(), b"0123456789");
+
+        let many = vec!["0-0"; 17].join(",");
+        let many = apply_range(Some(&format!("bytes={many}")), response());
+        assert_eq!(many.response_code(), &ResponseCode::Ok);
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+
+use crate::handler::Handler;
+use crate::middleware::{Middleware, Next};
+use crate::types::{Header, HttpVersion, Method, Request, Response, ResponseCode};
+use crate::uri::{percent_decode, Form, Uri};
+
+// Segment
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Param(String),
+    Wildcard(String),
+}
+
+// Pattern
+#[derive(Debug, Clone, PartialEq)]
+struct Pattern {
+    segments: Vec<Segment>,
+}
+impl Pattern {
+    fn parse(path: &str) -> Self {
+        let segments = split_path(path)
+            .map(|segment| {
+                if let Some(name) = segment.strip_prefix(':') {
+                    Segment::Param(name.to_string())
+                } else if let Some(name) = segment.strip_prefix('*') {
+                    Segment::Wildcard(name.to_string())
+                } else {
+                    Segment::Literal(segment.to_string())
+                }
+            })
+            .collect();
+        Self { segments }
+    }
+
+    fn matches(&self, parts: &[String]) -> Option<HashMap<String, String>> {
+        let mut params = HashMap::new();
+        for (index, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Literal(literal) => {
+                    if parts.get(index) != Some(literal) {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.insert(name.clone(), parts.get(index)?.to_string());
+                }
+                Segment::Wildcard(name) => {
+                    params.insert(name.clone(), parts[index..].join("/"));
+                    return Some(params);
+                }
+            }
+        }
+        if parts.len() != self.segments.len() {
+            return None;
+        }
+        Some(params)
+    }
+}
+
+// Route
+struct Route {
+    method: Method,
+    pattern: Pattern,
+    handler: Box<dyn Handler>,
+}
+
+// MountTarget
+struct MountTarget {
+    handler: Box<dyn Handler>,
+    fallback: Option<Box<dyn Handler>>,
+}
+impl Handler for MountTarget {
+    fn handle(&self, request: Request) -> Response {
+        match &self.fallback {
+            Some(fallback) => self
+                .handler
+                .try_handle(request)
+                .unwrap_or_else(|request| fallback.handle(*request)),
+            None => self.handler.handle(request),
+        }
+    }
+}
+
+// Mount
+pub struct Mount {
+    prefix: Vec<String>,
+    target: MountTarget,
+    middleware: Vec<Box<dyn Middleware>>,
+}
+impl Mount {
+    pub fn middleware<M: Middleware>(&mut self, middleware: M) -> &mut Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+    pub fn fallback<H: Handler>(&mut self, handler: H) -> &mut Self {
+        self.target.fallback = Some(Box::new(handler));
+        self
+    }
+
+    fn strip_prefix(&self, uri: &Uri) -> Option<String> {
+        let path = uri.normalized_path();
+        let mut parts = split_path(&path);
+        for segment in &self.prefix {
+            if parts.next().map(percent_decode).as_ref() != Some(segment) {
+                return None;
+            }
+        }
+        let remainder = format!("/{}", parts.collect::<Vec<&str>>().join("/"));
+        match uri.query() {
+            Some(query) => Some(format!("{remainder}?{query}")),
+            None => Some(remainder),
+        }
+    }
+
+    fn dispatch(&self, request: Request) -> Response {
+        Next::new(&self.middleware, &self.target).run(request)
+    }
+}
+
+// Router
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+    mounts: Vec<Mount>,
+    fallback: Option<Box<dyn Handler>>,
+}
+impl Router {
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            mounts: Vec::new(),
+            fallback: None,
+        }
+    }
+
+    pub fn mount<H: Handler>(&mut self, prefix: &str, handler: H) -> &mut Mount {
+        self.mounts.push(Mount {
+            prefix: split_path(prefix).map(String::from).collect(),
+            target: MountTarget {
+                handler: Box::new(handler),
+                fallback: None,
+            },
+            middleware: Vec::new(),
+        });
+        self.mounts.last_mut().unwrap()
+    }
+    pub fn fallback<H: Handler>(&mut self, handler: H) -> &mut Self {
+        self.fallback = Some(Box::new(handler));
+        self
+    }
+
+    pub fn route<H: Handler>(&mut self, method: Method, path: &str, handler: H) -> &mut Self {
+        self.routes.push(Route {
+            method,
+            pattern: Pattern::parse(path),
+            handler: Box::new(handler),
+        });
+        self
+    }
+    pub fn get<H: Handler>(&mut self, path: &str, handler: H) -> &mut Self {
+        self.route(Method::Get, path, handler)
+    }
+    pub fn head<H: Handler>(&mut self, path: &str, handler: H) -> &mut Self {
+        self.route(Method::Head, path, handler)
+    }
+    pub fn post<H: Handler>(&mut self, path: &str, handler: H) -> &mut Self {
+        self.route(Method::Post, path, handler)
+    }
+    pub fn put<H: Handler>(&mut self, path: &str, handler: H) -> &mut Self {
+        self.route(Method::Put, path, handler)
+    }
+    pub fn delete<H: Handler>(&mut self, path: &str, handler: H) -> &mut Self {
+        self.route(Method::Delete, path, handler)
+    }
+    pub fn options<H: Handler>(&mut self, path: &str, handler: H) -> &mut Self {
+        self.route(Method::Options, path, handler)
+    }
+    pub fn patch<H: Handler>(&mut self, path: &str, handler: H) -> &mut Self {
+        self.route(Method::Patch, path, handler)
+    }
+}
+impl Handler for Router {
+    fn handle(&self, request: Request) -> Response {
+        self.try_handle(request).unwrap_or_else(|request| {
+            Response::from_code(request.request_line.version, ResponseCode::NotFound)
+        })
+    }
+
+    fn try_handle(&self, mut request: Request) -> Result<Response, Box<Request>> {
+        let version = request.request_line.version.clone();
+        let Some(uri) = request.uri() else {
+            return Ok(Response::from_code(version, ResponseCode::BadRequest));
+        };
+        if uri.form() == Form::Asterisk {
+            let methods = self.routes.iter().map(|route| &route.method);
+            return Ok(options_response(version, methods));
+        }
+        let path = uri.segments();
+        let mut allowed = Vec::<&Method>::new();
+        let mut get = None;
+        for route in &self.routes {
+            let Some(params) = route.pattern.matches(&path) else {
+                continue;
+            };
+            if route.method == request.request_line.method {
+                request.params.extend(params);
+                return Ok(route.handler.handle(request));
+            }
+            if route.method == Method::Get && get.is_none() {
+                get = Some((route, params));
+            }
+            allowed.push(&route.method);
+        }
+        if let (Method::Head, Some((route, params))) = (&request.request_line.method, get) {
+            request.params.extend(params);
+            return Ok(route.handler.handle(request));
+        }
+        for mount in &self.mounts {
+            if let Some(remainder) = mount.strip_prefix(&uri) {
+                request.request_line.resource = remainder;
+                return Ok(mount.dispatch(request));
+            }
+        }
+        if allowed.is_empty() {
+            return match &self.fallback {
+                Some(fallback) => Ok(fallback.handle(request)),
+                None => Err(Box::new(request)),
+            };
+        }
+        if request.request_line.method == Method::Options {
+            return Ok(options_response(version, allowed.into_iter()));
+        }
+        let mut response = Response::from_code(version, ResponseCode::MethodNotAllowed);
+        response.add_header(Header::ALLOW, &allow_header(allowed.into_iter()));
+        Ok(response)
+    }
+}
+
+pub(crate) fn options_response<'a>(
+    version: HttpVersion,
+    methods: impl Iterator<Item = &'a Method>,
+) -> Response {
+    let mut response = Response::new(version, ResponseCode::NoContent, Vec::new());
+    response.add_header(Header::ALLOW, &allow_header(methods));
+    response
+}
+
+pub(crate) fn allow_header<'a>(methods: impl Iterator<Item = &'a Method>) -> String {
+    let mut allowed = Vec::<&Method>::new();
+    for method in methods {
+        if !allowed.contains(&method) {
+            allowed.push(method);
+        }
+    }
+    if allowed.contains(&&Method::Get) && !allowed.contains(&&Method::Head) {
+        allowed.push(&Method::Head);
+    }
+    if !allowed.contains(&&Method::Options) {
+        allowed.push(&Method::Options);
+    }
+    allowed
+        .iter()
+        .map(|method| method.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::Router;
+    use crate::handler::Handler;
+    use crate::middleware::Next;
+    use crate::types::{test_request, Header, Request, Response, ResponseCode};
+
+    fn echo_param(name: &'static str) -> impl Fn(Request) -> Response + Send + Sync {
+        move |request: Request| {
+            let value = request.param(name).unwrap_or_default().to_string();
+            Response::new(
+                request.request_line.version,
+                ResponseCode::Ok,
+                value.into_bytes(),
+            )
+        }
+    }
+
+    fn missing(request: Request) -> Response {
+        Response::new(
+            request.request_line.version,
+            ResponseCode::NotFound,
+            b"user 9 missing".to_vec(),
+        )
+    }
+
+    #[test]
+    pub fn route_with_params() {
+        let mut router = Router::new();
+        router.get("/users/:id", echo_param("id"));
+        router.get("/static/*rest", echo_param("rest"));
+
+        let response = router.handle(test_request("GET", "/users/42?full=1", &[]));
+        assert_eq!(response.body(), b"42");
+        let response = router.handle(test_request("GET", "/static/css/site.css", &[]));
+        assert_eq!(response.body(), b"css/site.css");
+        let response = router.handle(test_request("GET", "/static/./img/../a%20b.png", &[]));
+        assert_eq!(response.body(), b"a b.png");
+        let response = router.handle(test_request("GET", "/users/42/posts", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::NotFound);
+    }
+
+    #[test]
+    pub fn method_not_allowed() {
+        let mut router = Router::new();
+        router.get("/users/:id", echo_param("id"));
+        router.delete("/users/:id", echo_param("id"));
+
+        let response = router.handle(test_request("POST", "/users/42", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::MethodNotAllowed);
+        assert!(response
+            .headers()
+            .contains(&Header::new("Allow", "GET, DELETE, HEAD, OPTIONS")));
+    }
+
+    #[test]
+    pub fn implicit_head_and_options() {
+        let mut router = Router::new();
+        router.get("/users/:id", echo_param("id"));
+        router.patch("/users/:id", echo_param("id"));
+
+        let response = router.handle(test_request("HEAD", "/users/42", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::Ok);
+        assert_eq!(response.body(), b"42");
+
+        let response = router.handle(test_request("OPTIONS", "/users/42", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::NoContent);
+        assert_eq!(response.header("Allow"), Some("GET, PATCH, HEAD, OPTIONS"));
+
+        let response = router.handle(test_request("PROPFIND", "/users/42", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::MethodNotAllowed);
+        assert_eq!(response.header("Allow"), Some("GET, PATCH, HEAD, OPTIONS"));
+
+        router.delete("/posts/:id", echo_param("id"));
+        let response = router.handle(test_request("OPTIONS", "*", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::NoContent);
+        assert_eq!(
+            response.header("Allow"),
+            Some("GET, PATCH, DELETE, HEAD, OPTIONS")
+        );
+    }
+
+    #[test]
+    pub fn mounted_router() {
+        let mut users = Router::new();
+        users.get("/users/9", missing);
+        users.get("/users/:id", echo_param("id"));
+        let mut router = Router::new();
+        router
+            .mount("/api/v1", users)
+            .middleware(|request: Request, next: &Next| {
+                let mut response = next.run(request);
+                response.add_header("X-Api-Version", "1");
+                response
+            })
+            .fallback(|request: Request| {
+                Response::new(
+                    request.request_line.version,
+                    ResponseCode::NotFound,
+                    request.request_line.resource.into_bytes(),
+                )
+            });
+
+        let response = router.handle(test_request("GET", "/api/v1/users/7", &[]));
+        assert_eq!(response.body(), b"7");
+        assert!(response.has_header("X-Api-Version"));
+        let response = router.handle(test_request("GET", "/api/v1/users/9", &[]));
+        assert_eq!(response.body(), b"user 9 missing");
+        let response = router.handle(test_request("GET", "/api/v1/missing?q=1", &[]));
+        assert_eq!(response.body(), b"/missing?q=1");
+        assert!(response.has_header("X-Api-Version"));
+        let response = router.handle(test_request("GET", "/api/v2/users/7", &[]));
+        assert_eq!(response.body(), b"Not Found");
+    }
+}
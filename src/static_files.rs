@@ -0,0 +1,438 @@
+use std::fs::{self, File, Metadata};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::conditional::{precondition_response, EntityTag, Validators};
+use crate::date::format_http_date;
+use crate::handler::Handler;
+use crate::headers::Accept;
+use crate::range::apply_range;
+use crate::router::{allow_header, options_response};
+use crate::types::{BodyStream, Header, Method, Request, Response, ResponseCode};
+use crate::uri::{percent_decode, Uri};
+
+// StaticFiles
+pub struct StaticFiles {
+    root: PathBuf,
+    index: String,
+    autoindex: bool,
+}
+impl StaticFiles {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self {
+            root: root.into(),
+            index: String::from("index.html"),
+            autoindex: false,
+        }
+    }
+
+    pub fn index(mut self, index: &str) -> Self {
+        self.index = index.to_string();
+        self
+    }
+    pub fn autoindex(mut self, enabled: bool) -> Self {
+        self.autoindex = enabled;
+        self
+    }
+
+    pub(crate) fn resolve(&self, uri: &Uri) -> Result<PathBuf, ResponseCode> {
+        if uri.path_segments().iter().any(|segment| segment == "..") {
+            return Err(ResponseCode::Forbidden);
+        }
+        let mut path = self.root.clone();
+        for segment in uri.segments() {
+            if segment.contains(['/', '\\', '\0']) {
+                return Err(ResponseCode::Forbidden);
+            }
+            path.push(segment);
+        }
+        let root = self
+            .root
+            .canonicalize()
+            .map_err(|_| ResponseCode::NotFound)?;
+        let path = path.canonicalize().map_err(|_| ResponseCode::NotFound)?;
+        if !path.starts_with(&root) {
+            return Err(ResponseCode::Forbidden);
+        }
+        Ok(path)
+    }
+
+    fn serve_file(&self, request: &Request, path: &Path) -> Response {
+        let version = request.request_line.version.clone();
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(error) => return Response::from_code(version, error_code(&error)),
+        };
+        let validators = Validators {
+            etag: Some(file_etag(&metadata)),
+            last_modified: metadata.modified().ok(),
+        };
+        let stream = match File::open(path).and_then(BodyStream::from_file) {
+            Ok(stream) => stream,
+            Err(error) => return Response::from_code(version, error_code(&error)),
+        };
+        let mut response = Response::streaming(version, ResponseCode::Ok, stream);
+        response.add_header(Header::CONTENT_TYPE, mime_type(path));
+        validators.apply(&mut response);
+        if let Some(response_code) = validators.evaluate(request) {
+            return precondition_response(response, response_code);
+        }
+        let range = match request.request_line.method {
+            Method::Get if validators.if_range(request) => request.header(Header::RANGE),
+            _ => None,
+        };
+        apply_range(range, response)
+    }
+}
+impl Handler for StaticFiles {
+    fn handle(&self, request: Request) -> Response {
+        let version = request.request_line.version.clone();
+        match request.request_line.method {
+            Method::Get | Method::Head => (),
+            Method::Options => return options_response(version, [Method::Get].iter()),
+            _ => {
+                let mut response = Response::from_code(version, ResponseCode::MethodNotAllowed);
+                response.add_header(Header::ALLOW, &allow_header([Method::Get].iter()));
+                return response;
+            }
+        }
+        let Some(uri) = request.uri() else {
+            return Response::from_code(version, ResponseCode::BadRequest);
+        };
+        let path = match self.resolve(&uri) {
+            Ok(path) => path,
+            Err(code) => return Response::from_code(version, code),
+        };
+        if path.is_dir() {
+            let index = path.join(&self.index);
+            if index.is_file() {
+                return self.serve_file(&request, &index);
+            }
+            if self.autoindex {
+                return listing(&request, &uri, &path);
+            }
+            return Response::from_code(version, ResponseCode::NotFound);
+        }
+        self.serve_file(&request, &path)
+    }
+}
+
+fn error_code(error: &std::io::Error) -> ResponseCode {
+    match error.kind() {
+        ErrorKind::PermissionDenied => ResponseCode::Forbidden,
+        _ => ResponseCode::NotFound,
+    }
+}
+
+fn file_etag(metadata: &Metadata) -> EntityTag {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    EntityTag::strong(&format!(
+        "{:x}-{:x}.{:x}",
+        metadata.len(),
+        modified.as_secs(),
+        modified.subsec_nanos()
+    ))
+}
+
+// Entry
+struct Entry {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+fn listing(request: &Request, uri: &Uri, path: &Path) -> Response {
+    let version = request.request_line.version.clone();
+    let mut entries = match read_entries(path) {
+        Ok(entries) => entries,
+        Err(_) => return Response::from_code(version, ResponseCode::Forbidden),
+    };
+    entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    let wants_json = request
+        .headers
+        .typed::<Accept>()
+        .is_some_and(|accept| accept.quality("application/json") > accept.quality("text/html"));
+    let (body, content_type) = if wants_json {
+        (listing_json(&entries), "application/json")
+    } else {
+        let path = percent_decode(&uri.normalized_path());
+        (listing_html(&path, &entries), "text/html; charset=utf-8")
+    };
+    let mut response = Response::new(version, ResponseCode::Ok, body.into_bytes());
+    response.add_header(Header::CONTENT_TYPE, content_type);
+    response
+}
+
+fn read_entries(path: &Path) -> std::io::Result<Vec<Entry>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        entries.push(Entry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir: metadata.is_dir(),
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+        });
+    }
+    Ok(entries)
+}
+
+fn listing_html(path: &str, entries: &[Entry]) -> String {
+    let base = match path.rsplit('/').next() {
+        Some(last) if !last.is_empty() => format!("{}/", encode_href(last)),
+        _ => String::new(),
+    };
+    let title = escape_html(if path.is_empty() { "/" } else { path });
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><title>Index of {title}</title></head>\n<body>\n<h1>Index of {title}</h1>\n<table>\n<tr><th>Name</th><th>Size</th><th>Modified</th></tr>\n"
+    );
+    if !path.trim_matches('/').is_empty() {
+        html += &format!("<tr><td><a href=\"{base}../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        let size = if entry.is_dir {
+            String::from("-")
+        } else {
+            entry.size.to_string()
+        };
+        let modified = entry.modified.map(format_http_date).unwrap_or_default();
+        html += &format!(
+            "<tr><td><a href=\"{base}{}{suffix}\">{}{suffix}</a></td><td>{size}</td><td>{modified}</td></tr>\n",
+            encode_href(&entry.name),
+            escape_html(&entry.name),
+        );
+    }
+    html + "</table>\n</body>\n</html>\n"
+}
+
+fn listing_json(entries: &[Entry]) -> String {
+    let items = entries
+        .iter()
+        .map(|entry| {
+            let modified = match entry.modified {
+                Some(modified) => format!("\"{}\"", format_http_date(modified)),
+                None => String::from("null"),
+            };
+            format!(
+                "{{\"name\":\"{}\",\"type\":\"{}\",\"size\":{},\"modified\":{}}}",
+                escape_json(&entry.name),
+                if entry.is_dir { "directory" } else { "file" },
+                entry.size,
+                modified
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(",");
+    format!("[{items}]")
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn encode_href(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+pub fn mime_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "md" => "text/markdown; charset=utf-8",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "webp" => "image/webp",
+        "avif" => "image/avif",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        "ttf" => "font/ttf",
+        "otf" => "font/otf",
+        "mp3" => "audio/mpeg",
+        "ogg" => "audio/ogg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "pdf" => "application/pdf",
+        "zip" => "application/zip",
+        "gz" => "application/gzip",
+        "tar" => "application/x-tar",
+        "wasm" => "application/wasm",
+        _ => "application/octet-stream",
+    }
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use super::StaticFiles;
+    use crate::handler::Handler;
+    use crate::types::{test_request, Header, Response, ResponseCode};
+
+    fn document_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("rust_http_server_{name}"));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("docs")).unwrap();
+        fs::write(root.join("index.html"), "<h1>home</h1>").unwrap();
+        fs::write(root.join("docs/logo.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        root
+    }
+
+    fn read_body(mut response: Response) -> Vec<u8> {
+        match response.take_stream() {
+            Some(stream) => stream.flat_map(Result::unwrap).collect(),
+            None => response.body().to_vec(),
+        }
+    }
+
+    #[test]
+    pub fn serve_files() {
+        let files = StaticFiles::new(document_root("serve_files"));
+
+        let response = files.handle(test_request("GET", "/", &[]));
+        assert_eq!(response.stream_length(), Some(13));
+        assert_eq!(read_body(response), b"<h1>home</h1>");
+        let response = files.handle(test_request("GET", "/docs/logo.png?v=2", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::Ok);
+        assert!(response
+            .headers()
+            .contains(&Header::new("Content-type", "image/png")));
+        let mut request = test_request("GET", "/docs/logo.png", &[]);
+        request.add_header("Range", "bytes=1-");
+        let response = files.handle(request);
+        assert_eq!(response.response_code(), &ResponseCode::PartialContent);
+        let etag = response.header("ETag").unwrap().to_string();
+        assert_eq!(read_body(response), b"PNG");
+        let mut request = test_request("GET", "/docs/logo.png", &[]);
+        request.add_header("Range", "bytes=0-0,-1");
+        let response = files.handle(request);
+        let length = response.stream_length().unwrap();
+        let body = read_body(response);
+        assert_eq!(body.len() as u64, length);
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("Content-Range: bytes 3-3/4\r\n\r\nG\r\n"));
+        let mut request = test_request("GET", "/docs/logo.png", &[]);
+        request.add_header("If-None-Match", &etag);
+        let response = files.handle(request);
+        assert_eq!(response.response_code(), &ResponseCode::NotModified);
+        assert!(response.body().is_empty());
+        let response = files.handle(test_request("GET", "/docs/missing.png", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::NotFound);
+        let response = files.handle(test_request("GET", "/docs", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::NotFound);
+        let response = files.handle(test_request("OPTIONS", "/index.html", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::NoContent);
+        assert_eq!(response.header("Allow"), Some("GET, HEAD, OPTIONS"));
+        let response = files.handle(test_request("PUT", "/index.html", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::MethodNotAllowed);
+        assert_eq!(response.header("Allow"), Some("GET, HEAD, OPTIONS"));
+    }
+
+    #[test]
+    pub fn directory_listing() {
+        let files = StaticFiles::new(document_root("directory_listing")).autoindex(true);
+
+        let response = files.handle(test_request("GET", "/docs", &[]));
+        let html = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(html.contains("<a href=\"docs/logo.png\">logo.png</a>"));
+        let mut request = test_request("GET", "/docs/", &[]);
+        request.add_header("Accept", "application/json");
+        let response = files.handle(request);
+        assert!(response
+            .headers()
+            .contains(&Header::new("Content-type", "application/json")));
+        let json = String::from_utf8(response.body().to_vec()).unwrap();
+        assert!(json.starts_with("[{\"name\":\"logo.png\",\"type\":\"file\",\"size\":4,"));
+        let mut request = test_request("GET", "/docs/", &[]);
+        request.add_header("Accept", "text/html, application/json;q=0");
+        let response = files.handle(request);
+        assert!(response
+            .headers()
+            .contains(&Header::new("Content-type", "text/html; charset=utf-8")));
+        let mut request = test_request("GET", "/docs/", &[]);
+        request.add_header(
+            "Accept",
+            "text/html;q=0.1, */*;q=0.5, application/json;q=0.3",
+        );
+        let response = files.handle(request);
+        assert!(response
+            .headers()
+            .contains(&Header::new("Content-type", "application/json")));
+    }
+
+    #[test]
+    pub fn refuse_escapes() {
+        let root = document_root("refuse_escapes");
+        let files = StaticFiles::new(root.join("docs"));
+
+        let response = files.handle(test_request("GET", "/../index.html", &[]));
+        assert_eq!(response.response_code(), &ResponseCode::Forbidden);
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(root.join("index.html"), root.join("docs/escape")).unwrap();
+            let response = files.handle(test_request("GET", "/escape", &[]));
+            assert_eq!(response.response_code(), &ResponseCode::Forbidden);
+        }
+    }
+}
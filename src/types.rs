@@ -1,9 +1,16 @@
 use std::cmp::PartialEq;
+use std::collections::HashMap;
 use std::fmt::{self, Display};
-use std::str::FromStr;
+use std::fs::File;
+use std::io::{ErrorKind, IoSlice, Read, Write};
+
+use crate::parser::{ParseError, Parser, Status};
+use crate::uri::{Form, Uri};
+
+const STREAM_CHUNK_SIZE: usize = 8192;
 
 // Method
-#[derive(PartialEq, Debug, Clone, Copy)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum Method {
     Get,
     Head,
@@ -11,16 +18,24 @@ pub enum Method {
     Put,
     Delete,
     Options,
+    Patch,
+    Trace,
+    Connect,
+    Extension(String),
 }
 impl Method {
     pub fn from_string(string: &str) -> Option<Self> {
-        match string.to_uppercase().as_str() {
+        match string {
             "GET" => Some(Self::Get),
             "HEAD" => Some(Self::Head),
             "POST" => Some(Self::Post),
             "PUT" => Some(Self::Put),
             "DELETE" => Some(Self::Delete),
             "OPTIONS" => Some(Self::Options),
+            "PATCH" => Some(Self::Patch),
+            "TRACE" => Some(Self::Trace),
+            "CONNECT" => Some(Self::Connect),
+            token if is_token(token) => Some(Self::Extension(token.to_string())),
             _ => None,
         }
     }
@@ -34,80 +49,334 @@ impl Display for Method {
             Self::Put => write!(f, "PUT"),
             Self::Delete => write!(f, "DELETE"),
             Self::Options => write!(f, "OPTIONS"),
+            Self::Patch => write!(f, "PATCH"),
+            Self::Trace => write!(f, "TRACE"),
+            Self::Connect => write!(f, "CONNECT"),
+            Self::Extension(token) => write!(f, "{token}"),
         }
     }
 }
 
+pub(crate) fn is_token(string: &str) -> bool {
+    !string.is_empty()
+        && string
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&byte))
+}
+
 // ResponseCode
 #[derive(PartialEq, Debug, Clone)]
 pub enum ResponseCode {
-    Unknown = 0,
-    Ok = 200,
-    BadRequest = 400,
-    Unauthorized = 401,
-    Forbidden = 403,
-    NotFound = 404,
-    MethodNotAllowed = 405,
-    InternalServerError = 500,
-    NotImplemented = 501,
-    BadGateway = 502,
-    ServiceUnavailable = 503,
-    GatewayTimeout = 504,
-    HttpVersionNotSupported = 505,
+    Continue,
+    SwitchingProtocols,
+    Processing,
+    EarlyHints,
+    Ok,
+    Created,
+    Accepted,
+    NonAuthoritativeInformation,
+    NoContent,
+    ResetContent,
+    PartialContent,
+    MultiStatus,
+    AlreadyReported,
+    ImUsed,
+    MultipleChoices,
+    MovedPermanently,
+    Found,
+    SeeOther,
+    NotModified,
+    UseProxy,
+    TemporaryRedirect,
+    PermanentRedirect,
+    BadRequest,
+    Unauthorized,
+    PaymentRequired,
+    Forbidden,
+    NotFound,
+    MethodNotAllowed,
+    NotAcceptable,
+    ProxyAuthenticationRequired,
+    RequestTimeout,
+    Conflict,
+    Gone,
+    LengthRequired,
+    PreconditionFailed,
+    ContentTooLarge,
+    UriTooLong,
+    UnsupportedMediaType,
+    RangeNotSatisfiable,
+    ExpectationFailed,
+    MisdirectedRequest,
+    UnprocessableContent,
+    Locked,
+    FailedDependency,
+    TooEarly,
+    UpgradeRequired,
+    PreconditionRequired,
+    TooManyRequests,
+    RequestHeaderFieldsTooLarge,
+    UnavailableForLegalReasons,
+    InternalServerError,
+    NotImplemented,
+    BadGateway,
+    ServiceUnavailable,
+    GatewayTimeout,
+    HttpVersionNotSupported,
+    VariantAlsoNegotiates,
+    InsufficientStorage,
+    LoopDetected,
+    NotExtended,
+    NetworkAuthenticationRequired,
+    Other(u16, String),
 }
 impl ResponseCode {
     pub fn new(value: usize) -> Self {
-        match value {
+        let code = u16::try_from(value).unwrap_or(u16::MAX);
+        match code {
+            100 => Self::Continue,
+            101 => Self::SwitchingProtocols,
+            102 => Self::Processing,
+            103 => Self::EarlyHints,
             200 => Self::Ok,
+            201 => Self::Created,
+            202 => Self::Accepted,
+            203 => Self::NonAuthoritativeInformation,
+            204 => Self::NoContent,
+            205 => Self::ResetContent,
+            206 => Self::PartialContent,
+            207 => Self::MultiStatus,
+            208 => Self::AlreadyReported,
+            226 => Self::ImUsed,
+            300 => Self::MultipleChoices,
+            301 => Self::MovedPermanently,
+            302 => Self::Found,
+            303 => Self::SeeOther,
+            304 => Self::NotModified,
+            305 => Self::UseProxy,
+            307 => Self::TemporaryRedirect,
+            308 => Self::PermanentRedirect,
             400 => Self::BadRequest,
             401 => Self::Unauthorized,
+            402 => Self::PaymentRequired,
             403 => Self::Forbidden,
             404 => Self::NotFound,
             405 => Self::MethodNotAllowed,
+            406 => Self::NotAcceptable,
+            407 => Self::ProxyAuthenticationRequired,
+            408 => Self::RequestTimeout,
+            409 => Self::Conflict,
+            410 => Self::Gone,
+            411 => Self::LengthRequired,
+            412 => Self::PreconditionFailed,
+            413 => Self::ContentTooLarge,
+            414 => Self::UriTooLong,
+            415 => Self::UnsupportedMediaType,
+            416 => Self::RangeNotSatisfiable,
+            417 => Self::ExpectationFailed,
+            421 => Self::MisdirectedRequest,
+            422 => Self::UnprocessableContent,
+            423 => Self::Locked,
+            424 => Self::FailedDependency,
+            425 => Self::TooEarly,
+            426 => Self::UpgradeRequired,
+            428 => Self::PreconditionRequired,
+            429 => Self::TooManyRequests,
+            431 => Self::RequestHeaderFieldsTooLarge,
+            451 => Self::UnavailableForLegalReasons,
             500 => Self::InternalServerError,
             501 => Self::NotImplemented,
             502 => Self::BadGateway,
             503 => Self::ServiceUnavailable,
             504 => Self::GatewayTimeout,
             505 => Self::HttpVersionNotSupported,
-            _ => Self::Unknown,
+            506 => Self::VariantAlsoNegotiates,
+            507 => Self::InsufficientStorage,
+            508 => Self::LoopDetected,
+            510 => Self::NotExtended,
+            511 => Self::NetworkAuthenticationRequired,
+            code => Self::Other(code, class_reason_phrase(code).to_string()),
         }
     }
-    pub fn to_string(&self) -> Option<&'static str> {
-        match self {
-            Self::Ok => Some("200 Ok"),
-            Self::BadRequest => Some("400 Bad Request"),
-            Self::Unauthorized => Some("401 Unauthorized"),
-            Self::Forbidden => Some("403 Forbidden"),
-            Self::NotFound => Some("404 Not Found"),
-            Self::MethodNotAllowed => Some("405 Method Not Allowed"),
-            Self::InternalServerError => Some("500 Internal Server Error"),
-            Self::NotImplemented => Some("501 Not Implemented"),
-            Self::BadGateway => Some("502 Bad Gateway"),
-            Self::ServiceUnavailable => Some("503 Service Unavailable"),
-            Self::GatewayTimeout => Some("504 Gateway Timeout"),
-            Self::HttpVersionNotSupported => Some("505 Http Version Not Supported"),
-            _ => None,
+    pub fn custom(code: u16, reason_phrase: &str) -> Option<Self> {
+        match Self::new(code as usize) {
+            response_code if !response_code.is_valid() => None,
+            Self::Other(code, _) if !reason_phrase.is_empty() => {
+                Some(Self::Other(code, reason_phrase.to_string()))
+            }
+            response_code => Some(response_code),
         }
     }
 
-    pub fn reason_phrase(&self) -> Option<String> {
+    pub fn code(&self) -> u16 {
         match self {
-            Self::Ok => Some(String::from("Ok")),
-            Self::BadRequest => Some(String::from("Bad Request")),
-            Self::Unauthorized => Some(String::from("Unauthorized")),
-            Self::Forbidden => Some(String::from("Forbidden")),
-            Self::NotFound => Some(String::from("Not Found")),
-            Self::MethodNotAllowed => Some(String::from("Method Not Allowed")),
-            Self::InternalServerError => Some(String::from("Internal Server Error")),
-            Self::NotImplemented => Some(String::from("Not Implemented")),
-            Self::BadGateway => Some(String::from("Bad Gateway")),
-            Self::ServiceUnavailable => Some(String::from("Service Unavailable")),
-            Self::GatewayTimeout => Some(String::from("Gate wayTime")),
-            Self::HttpVersionNotSupported => Some(String::from("Http Version Not Supported")),
-            _ => None,
+            Self::Continue => 100,
+            Self::SwitchingProtocols => 101,
+            Self::Processing => 102,
+            Self::EarlyHints => 103,
+            Self::Ok => 200,
+            Self::Created => 201,
+            Self::Accepted => 202,
+            Self::NonAuthoritativeInformation => 203,
+            Self::NoContent => 204,
+            Self::ResetContent => 205,
+            Self::PartialContent => 206,
+            Self::MultiStatus => 207,
+            Self::AlreadyReported => 208,
+            Self::ImUsed => 226,
+            Self::MultipleChoices => 300,
+            Self::MovedPermanently => 301,
+            Self::Found => 302,
+            Self::SeeOther => 303,
+            Self::NotModified => 304,
+            Self::UseProxy => 305,
+            Self::TemporaryRedirect => 307,
+            Self::PermanentRedirect => 308,
+            Self::BadRequest => 400,
+            Self::Unauthorized => 401,
+            Self::PaymentRequired => 402,
+            Self::Forbidden => 403,
+            Self::NotFound => 404,
+            Self::MethodNotAllowed => 405,
+            Self::NotAcceptable => 406,
+            Self::ProxyAuthenticationRequired => 407,
+            Self::RequestTimeout => 408,
+            Self::Conflict => 409,
+            Self::Gone => 410,
+            Self::LengthRequired => 411,
+            Self::PreconditionFailed => 412,
+            Self::ContentTooLarge => 413,
+            Self::UriTooLong => 414,
+            Self::UnsupportedMediaType => 415,
+            Self::RangeNotSatisfiable => 416,
+            Self::ExpectationFailed => 417,
+            Self::MisdirectedRequest => 421,
+            Self::UnprocessableContent => 422,
+            Self::Locked => 423,
+            Self::FailedDependency => 424,
+            Self::TooEarly => 425,
+            Self::UpgradeRequired => 426,
+            Self::PreconditionRequired => 428,
+            Self::TooManyRequests => 429,
+            Self::RequestHeaderFieldsTooLarge => 431,
+            Self::UnavailableForLegalReasons => 451,
+            Self::InternalServerError => 500,
+            Self::NotImplemented => 501,
+            Self::BadGateway => 502,
+            Self::ServiceUnavailable => 503,
+            Self::GatewayTimeout => 504,
+            Self::HttpVersionNotSupported => 505,
+            Self::VariantAlsoNegotiates => 506,
+            Self::InsufficientStorage => 507,
+            Self::LoopDetected => 508,
+            Self::NotExtended => 510,
+            Self::NetworkAuthenticationRequired => 511,
+            Self::Other(code, _) => *code,
         }
     }
+    pub fn reason_phrase(&self) -> &str {
+        match self {
+            Self::Continue => "Continue",
+            Self::SwitchingProtocols => "Switching Protocols",
+            Self::Processing => "Processing",
+            Self::EarlyHints => "Early Hints",
+            Self::Ok => "OK",
+            Self::Created => "Created",
+            Self::Accepted => "Accepted",
+            Self::NonAuthoritativeInformation => "Non-Authoritative Information",
+            Self::NoContent => "No Content",
+            Self::ResetContent => "Reset Content",
+            Self::PartialContent => "Partial Content",
+            Self::MultiStatus => "Multi-Status",
+            Self::AlreadyReported => "Already Reported",
+            Self::ImUsed => "IM Used",
+            Self::MultipleChoices => "Multiple Choices",
+            Self::MovedPermanently => "Moved Permanently",
+            Self::Found => "Found",
+            Self::SeeOther => "See Other",
+            Self::NotModified => "Not Modified",
+            Self::UseProxy => "Use Proxy",
+            Self::TemporaryRedirect => "Temporary Redirect",
+            Self::PermanentRedirect => "Permanent Redirect",
+            Self::BadRequest => "Bad Request",
+            Self::Unauthorized => "Unauthorized",
+            Self::PaymentRequired => "Payment Required",
+            Self::Forbidden => "Forbidden",
+            Self::NotFound => "Not Found",
+            Self::MethodNotAllowed => "Method Not Allowed",
+            Self::NotAcceptable => "Not Acceptable",
+            Self::ProxyAuthenticationRequired => "Proxy Authentication Required",
+            Self::RequestTimeout => "Request Timeout",
+            Self::Conflict => "Conflict",
+            Self::Gone => "Gone",
+            Self::LengthRequired => "Length Required",
+            Self::PreconditionFailed => "Precondition Failed",
+            Self::ContentTooLarge => "Content Too Large",
+            Self::UriTooLong => "URI Too Long",
+            Self::UnsupportedMediaType => "Unsupported Media Type",
+            Self::RangeNotSatisfiable => "Range Not Satisfiable",
+            Self::ExpectationFailed => "Expectation Failed",
+            Self::MisdirectedRequest => "Misdirected Request",
+            Self::UnprocessableContent => "Unprocessable Content",
+            Self::Locked => "Locked",
+            Self::FailedDependency => "Failed Dependency",
+            Self::TooEarly => "Too Early",
+            Self::UpgradeRequired => "Upgrade Required",
+            Self::PreconditionRequired => "Precondition Required",
+            Self::TooManyRequests => "Too Many Requests",
+            Self::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
+            Self::UnavailableForLegalReasons => "Unavailable For Legal Reasons",
+            Self::InternalServerError => "Internal Server Error",
+            Self::NotImplemented => "Not Implemented",
+            Self::BadGateway => "Bad Gateway",
+            Self::ServiceUnavailable => "Service Unavailable",
+            Self::GatewayTimeout => "Gateway Timeout",
+            Self::HttpVersionNotSupported => "HTTP Version Not Supported",
+            Self::VariantAlsoNegotiates => "Variant Also Negotiates",
+            Self::InsufficientStorage => "Insufficient Storage",
+            Self::LoopDetected => "Loop Detected",
+            Self::NotExtended => "Not Extended",
+            Self::NetworkAuthenticationRequired => "Network Authentication Required",
+            Self::Other(_, reason_phrase) => reason_phrase,
+        }
+    }
+
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.code())
+    }
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.code())
+    }
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.code())
+    }
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.code())
+    }
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.code())
+    }
+    pub fn is_valid(&self) -> bool {
+        (100..600).contains(&self.code())
+    }
+    pub fn allows_body(&self) -> bool {
+        !self.is_informational() && !matches!(self, Self::NoContent | Self::NotModified)
+    }
+}
+fn class_reason_phrase(code: u16) -> &'static str {
+    match code / 100 {
+        1 => "Informational",
+        2 => "Success",
+        3 => "Redirection",
+        4 => "Client Error",
+        5 => "Server Error",
+        _ => "",
+    }
+}
+impl Display for ResponseCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:03} {}", self.code(), self.reason_phrase())
+    }
 }
 // RequestLine
 #[derive(Debug, Clone)]
@@ -125,34 +394,34 @@ impl RequestLine {
         }
     }
 
-    pub fn from_string(string: &String) -> Option<Self> {
-        let mut iter = string.split_whitespace();
-        let size = iter.clone().count();
-        if size != 3 {
-            println!("expected size 3 , got {}", size);
-            return None;
-        }
-        let method = Method::from_string(iter.next().unwrap());
-        match method {
-            None => {
-                println!("unable to parse method");
-                return None;
-            }
-            Some(_) => (),
+    pub fn from_string(string: &str) -> Result<Self, ParseError> {
+        let parts: Vec<&str> = string.split_whitespace().collect();
+        let [method, resource, version] = parts[..] else {
+            return Err(ParseError::MalformedRequestLine);
+        };
+        let method = Method::from_string(method)
+            .ok_or_else(|| ParseError::InvalidMethod(method.to_string()))?;
+        let valid_target = match Uri::parse(resource).map(|uri| uri.form()) {
+            Some(Form::Origin | Form::Absolute) => method != Method::Connect,
+            Some(Form::Authority) => method == Method::Connect,
+            Some(Form::Asterisk) => method == Method::Options,
+            None => false,
         };
-        let resource = iter.next().unwrap().to_string();
-        let version = HttpVersion::from_string(iter.next().unwrap());
-        match version {
-            None => {
-                println!("unable to parse version");
-                return None;
+        if !valid_target {
+            return Err(ParseError::InvalidTarget(resource.to_string()));
+        }
+        let version = match HttpVersion::from_string(version) {
+            Some(parsed) if parsed.is_http1() => parsed,
+            Some(_) => return Err(ParseError::UnsupportedVersion(version.to_string())),
+            None if version_number(version).is_some() => {
+                return Err(ParseError::UnsupportedVersion(version.to_string()))
             }
-            Some(_) => (),
+            None => return Err(ParseError::InvalidVersion(version.to_string())),
         };
-        Some(Self {
-            version: version.unwrap(),
-            method: method.unwrap(),
-            resource,
+        Ok(Self {
+            version,
+            method,
+            resource: resource.to_string(),
         })
     }
 }
@@ -176,11 +445,10 @@ impl StatusLine {
             response_code: ResponseCode::new(code),
         }
     }
-
-    pub fn to_string(&self) -> String {
-        let s = String::from(self.version.to_string()) + " ";
-        let s = s + self.response_code.to_string().unwrap();
-        s
+}
+impl Display for StatusLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.version.to_string(), self.response_code)
     }
 }
 impl PartialEq for StatusLine {
@@ -196,38 +464,177 @@ pub struct Header {
     field_value: String,
 }
 impl Header {
+    pub const ACCEPT: &'static str = "Accept";
+    pub const ACCEPT_ENCODING: &'static str = "Accept-Encoding";
+    pub const ACCEPT_RANGES: &'static str = "Accept-Ranges";
+    pub const ALLOW: &'static str = "Allow";
+    pub const AUTHORIZATION: &'static str = "Authorization";
+    pub const CACHE_CONTROL: &'static str = "Cache-Control";
+    pub const CONNECTION: &'static str = "Connection";
+    pub const CONTENT_ENCODING: &'static str = "Content-Encoding";
+    pub const CONTENT_LENGTH: &'static str = "Content-Length";
+    pub const CONTENT_RANGE: &'static str = "Content-Range";
+    pub const CONTENT_TYPE: &'static str = "Content-Type";
+    pub const DATE: &'static str = "Date";
+    pub const ETAG: &'static str = "ETag";
+    pub const HOST: &'static str = "Host";
+    pub const IF_MATCH: &'static str = "If-Match";
+    pub const IF_MODIFIED_SINCE: &'static str = "If-Modified-Since";
+    pub const IF_NONE_MATCH: &'static str = "If-None-Match";
+    pub const IF_RANGE: &'static str = "If-Range";
+    pub const IF_UNMODIFIED_SINCE: &'static str = "If-Unmodified-Since";
+    pub const LAST_MODIFIED: &'static str = "Last-Modified";
+    pub const LOCATION: &'static str = "Location";
+    pub const RANGE: &'static str = "Range";
+    pub const TRAILER: &'static str = "Trailer";
+    pub const TRANSFER_ENCODING: &'static str = "Transfer-Encoding";
+    pub const USER_AGENT: &'static str = "User-Agent";
+
     pub fn new(field_name: &str, field_value: &str) -> Self {
         Self {
             field_name: String::from(field_name),
             field_value: String::from(field_value),
         }
     }
-    pub fn to_string(&self) -> String {
-        format!("{}:{}", self.field_name, self.field_value)
+    pub fn name(&self) -> &str {
+        &self.field_name
+    }
+    pub fn value(&self) -> &str {
+        &self.field_value
+    }
+    pub fn is(&self, field_name: &str) -> bool {
+        self.field_name
+            .trim()
+            .eq_ignore_ascii_case(field_name.trim())
     }
 }
 impl PartialEq for Header {
     fn eq(&self, other: &Header) -> bool {
-        return other.field_name.trim() == self.field_name.trim()
-            && other.field_value.trim() == self.field_value.trim();
+        self.is(&other.field_name) && other.field_value.trim() == self.field_value.trim()
     }
 }
 impl PartialEq<Header> for &Header {
     fn eq(&self, other: &Header) -> bool {
-        return other.field_name.trim() == self.field_name.trim()
-            && other.field_value.trim() == self.field_value.trim();
+        *self == other
     }
 }
 impl PartialEq<Header> for &str {
     fn eq(&self, other: &Header) -> bool {
-        let items: Vec<&str> = self.split(":").collect();
-        return other.field_name.trim() == items[0].trim()
-            && other.field_value.trim() == items[1].trim();
+        let Some((field_name, field_value)) = self.split_once(':') else {
+            return false;
+        };
+        other.is(field_name) && other.field_value.trim() == field_value.trim()
     }
 }
 impl Display for Header {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{} : {}", self.field_name, self.field_value)
+        write!(f, "{}: {}", self.field_name, self.field_value)
+    }
+}
+
+// HeaderMap
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeaderMap {
+    headers: Vec<Header>,
+}
+impl HeaderMap {
+    pub fn new() -> Self {
+        Self {
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.headers.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, Header> {
+        self.headers.iter()
+    }
+    pub fn contains(&self, header: &Header) -> bool {
+        self.headers.contains(header)
+    }
+    pub fn contains_key(&self, field_name: &str) -> bool {
+        self.headers.iter().any(|header| header.is(field_name))
+    }
+
+    pub fn get(&self, field_name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|header| header.is(field_name))
+            .map(Header::value)
+    }
+    pub fn get_all<'a>(&'a self, field_name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers
+            .iter()
+            .filter(move |header| header.is(field_name))
+            .map(Header::value)
+    }
+
+    pub fn insert(&mut self, field_name: &str, field_value: &str) {
+        let mut found = false;
+        self.headers.retain_mut(|header| {
+            if !header.is(field_name) {
+                return true;
+            }
+            if found {
+                return false;
+            }
+            found = true;
+            *header = Header::new(field_name, field_value);
+            true
+        });
+        if !found {
+            self.append(field_name, field_value);
+        }
+    }
+    pub fn append(&mut self, field_name: &str, field_value: &str) {
+        self.push(Header::new(field_name, field_value));
+    }
+    pub fn push(&mut self, header: Header) {
+        self.headers.push(header);
+    }
+    pub(crate) fn fold_last(&mut self, continuation: &str) -> bool {
+        let Some(header) = self.headers.last_mut() else {
+            return false;
+        };
+        header.field_value = format!("{} {continuation}", header.field_value)
+            .trim()
+            .to_string();
+        true
+    }
+    pub fn remove(&mut self, field_name: &str) -> Option<String> {
+        let removed = self.get(field_name).map(String::from);
+        self.headers.retain(|header| !header.is(field_name));
+        removed
+    }
+}
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = &'a Header;
+    type IntoIter = std::slice::Iter<'a, Header>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.headers.iter()
+    }
+}
+impl IntoIterator for HeaderMap {
+    type Item = Header;
+    type IntoIter = std::vec::IntoIter<Header>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.headers.into_iter()
+    }
+}
+impl Extend<Header> for HeaderMap {
+    fn extend<T: IntoIterator<Item = Header>>(&mut self, headers: T) {
+        self.headers.extend(headers);
+    }
+}
+impl FromIterator<Header> for HeaderMap {
+    fn from_iter<T: IntoIterator<Item = Header>>(headers: T) -> Self {
+        Self {
+            headers: headers.into_iter().collect(),
+        }
     }
 }
 
@@ -241,14 +648,17 @@ pub enum HttpVersion {
 }
 impl HttpVersion {
     pub fn from_string(string: &str) -> Option<Self> {
-        match string {
-            "HTTP/1.0" => Some(Self::HttpV1_0),
-            "HTTP/1.1" => Some(Self::HttpV1_1),
-            "HTTP/2.0" => Some(Self::HttpV2_0),
-            "HTTP/3.0" => Some(Self::HttpV3_0),
+        match version_number(string)? {
+            (1, 0) => Some(Self::HttpV1_0),
+            (1, _) => Some(Self::HttpV1_1),
+            (2, 0) => Some(Self::HttpV2_0),
+            (3, 0) => Some(Self::HttpV3_0),
             _ => None,
         }
     }
+    pub fn is_http1(&self) -> bool {
+        matches!(self, Self::HttpV1_0 | Self::HttpV1_1)
+    }
     pub fn to_string(&self) -> &'static str {
         match self {
             Self::HttpV1_0 => "HTTP/1.0",
@@ -259,114 +669,167 @@ impl HttpVersion {
     }
 }
 
+fn version_number(string: &str) -> Option<(u8, u8)> {
+    let (major, minor) = string.strip_prefix("HTTP/")?.split_once('.')?;
+    let digit = |digits: &str| match digits.as_bytes() {
+        [digit] if digit.is_ascii_digit() => Some(digit - b'0'),
+        _ => None,
+    };
+    Some((digit(major)?, digit(minor)?))
+}
+
 // Request
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Request {
     pub request_line: RequestLine,
-    pub headers: Vec<Header>,
+    pub headers: HeaderMap,
     pub body: Vec<u8>,
+    pub trailers: HeaderMap,
+    pub params: HashMap<String, String>,
+    pub close: bool,
 }
 impl Request {
-    pub fn parse_from_string(request: &String) -> Option<Self> {
-        let mut split = request.split("\r\n");
-        let request_line = match split.next() {
-            Some(maybe_request_line) => {
-                match RequestLine::from_string(&maybe_request_line.to_string()) {
-                    Some(valid_request_line) => valid_request_line,
-                    None => return None,
-                }
-            }
-            None => return None,
-        };
-        let mut headers = Vec::<Header>::new();
-        loop {
-            match split.next() {
-                Some(header_string) => {
-                    if header_string == "" {
-                        break;
-                    }
-                    let header_split: Vec<&str> = header_string.split(':').collect();
-                    if header_split.len() < 1 {
-                        continue;
-                    }
-                    let header = Header {
-                        field_name: String::from_str(header_split[0]).unwrap(),
-                        field_value: if header_split.len() > 1 {
-                            String::from_str(&header_split[1..].join(":").trim()).unwrap()
-                        } else {
-                            String::new()
-                        },
-                    };
-                    headers.push(header);
-                }
-                None => {
-                    break;
-                }
-            }
+    pub fn new(request_line: RequestLine) -> Self {
+        Self {
+            request_line,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            trailers: HeaderMap::new(),
+            params: HashMap::new(),
+            close: false,
         }
-        let body = Vec::<u8>::with_capacity(0);
+    }
+    pub fn parse_from_string(request: &str) -> Result<Self, ParseError> {
+        match Parser::new().parse(request.as_bytes())? {
+            (_, Status::Complete(request)) => Ok(request),
+            (_, Status::Incomplete) => Err(ParseError::Incomplete),
+        }
+    }
+    pub fn parse_from_str(request: &'static str) -> Result<Self, ParseError> {
+        Self::parse_from_string(request)
+    }
+    pub fn add_header(&mut self, field_name: &str, field_value: &str) {
+        self.headers.append(field_name, field_value);
+    }
+    pub fn header(&self, field_name: &str) -> Option<&str> {
+        self.headers.get(field_name)
+    }
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(|value| value.as_str())
+    }
+    pub fn uri(&self) -> Option<Uri> {
+        Uri::parse(&self.request_line.resource)
+    }
+}
+#[cfg(test)]
+pub(crate) fn test_request(method: &str, target: &str, headers: &[(&str, &str)]) -> Request {
+    let mut request = format!("{method} {target} HTTP/1.1\r\n");
+    for (field_name, field_value) in headers {
+        request += &format!("{field_name}: {field_value}\r\n");
+    }
+    Request::parse_from_string(&(request + "\r\n")).unwrap()
+}
+// BodyStream
+type Chunks = Box<dyn Iterator<Item = std::io::Result<Vec<u8>>> + Send>;
+type Trailers = Box<dyn FnOnce() -> Vec<Header> + Send>;
 
-        Some(Request {
-            request_line,
-            headers,
-            body,
-        })
+pub struct BodyStream {
+    chunks: Chunks,
+    length: Option<u64>,
+    file: Option<File>,
+    trailers: Option<Trailers>,
+}
+impl BodyStream {
+    pub fn from_chunks<I>(chunks: I) -> Self
+    where
+        I: IntoIterator<Item = Vec<u8>>,
+        I::IntoIter: Send + 'static,
+    {
+        Self {
+            chunks: Box::new(chunks.into_iter().map(Ok)),
+            length: None,
+            file: None,
+            trailers: None,
+        }
     }
-    pub fn parse_from_str(request: &'static str) -> Option<Self> {
-        let mut split = request.split("\r\n");
-        let request_line = match split.next() {
-            Some(maybe_request_line) => {
-                match RequestLine::from_string(&maybe_request_line.to_string()) {
-                    Some(valid_request_line) => valid_request_line,
-                    None => return None,
-                }
-            }
-            None => return None,
-        };
-        let mut headers = Vec::<Header>::new();
-        loop {
-            match split.next() {
-                Some(header_string) => {
-                    if header_string == "" {
-                        break;
-                    }
-                    let header_split: Vec<&str> = header_string.split(':').collect();
-                    if header_split.len() < 1 {
-                        continue;
-                    }
-                    let header = Header {
-                        field_name: String::from_str(header_split[0]).unwrap(),
-                        field_value: if header_split.len() > 1 {
-                            String::from_str(&header_split[1..].join(":").trim()).unwrap()
-                        } else {
-                            String::new()
-                        },
-                    };
-                    headers.push(header);
-                }
-                None => {
-                    break;
+    pub fn from_reader<R: Read + Send + 'static>(mut reader: R) -> Self {
+        let chunks = std::iter::from_fn(move || {
+            let mut chunk = vec![0; STREAM_CHUNK_SIZE];
+            match reader.read(&mut chunk) {
+                Ok(0) => None,
+                Ok(size) => {
+                    chunk.truncate(size);
+                    Some(Ok(chunk))
                 }
+                Err(error) => Some(Err(error)),
             }
+        });
+        Self {
+            chunks: Box::new(chunks),
+            length: None,
+            file: None,
+            trailers: None,
         }
-        let body = Vec::<u8>::with_capacity(0);
+    }
+    pub fn from_file(file: File) -> std::io::Result<Self> {
+        let length = file.metadata()?.len();
+        let reader = file.try_clone()?.take(length);
+        let mut stream = Self::from_reader(reader).sized(length);
+        stream.file = Some(file);
+        Ok(stream)
+    }
+    pub fn sized(mut self, length: u64) -> Self {
+        self.length = Some(length);
+        self
+    }
+    pub fn trailers<F>(mut self, trailers: F) -> Self
+    where
+        F: FnOnce() -> Vec<Header> + Send + 'static,
+    {
+        self.trailers = Some(Box::new(trailers));
+        self
+    }
 
-        Some(Request {
-            request_line,
-            headers,
-            body,
-        })
+    pub fn length(&self) -> Option<u64> {
+        self.length
     }
-    pub fn add_header(mut self, field_name: &String, field_value: &String) {
-        self.headers.push(Header::new(field_name, field_value));
+    pub fn is_seekable(&self) -> bool {
+        self.file.is_some()
+    }
+    pub(crate) fn take_file(&mut self) -> Option<File> {
+        self.file.take()
+    }
+    pub fn take_trailers(&mut self) -> Vec<Header> {
+        self.trailers
+            .take()
+            .map(|trailers| trailers())
+            .unwrap_or_default()
     }
 }
+impl Iterator for BodyStream {
+    type Item = std::io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.chunks.next()
+    }
+}
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyStream")
+            .field("length", &self.length)
+            .field("seekable", &self.is_seekable())
+            .field("trailers", &self.trailers.is_some())
+            .finish()
+    }
+}
+
 // Response
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Response {
     status_line: StatusLine,
-    headers: Vec<Header>,
+    headers: HeaderMap,
     body: Vec<u8>,
+    stream: Option<Box<BodyStream>>,
 }
 impl Response {
     pub fn new(version: HttpVersion, response_code: ResponseCode, body: Vec<u8>) -> Self {
@@ -375,26 +838,128 @@ impl Response {
                 version,
                 response_code,
             },
-            headers: Vec::<Header>::new(),
+            headers: HeaderMap::new(),
             body,
+            stream: None,
         }
     }
+    pub fn streaming(
+        version: HttpVersion,
+        response_code: ResponseCode,
+        stream: BodyStream,
+    ) -> Self {
+        let mut response = Self::new(version, response_code, Vec::new());
+        response.stream = Some(Box::new(stream));
+        response
+    }
+    pub fn from_code(version: HttpVersion, response_code: ResponseCode) -> Self {
+        let body = response_code.reason_phrase().as_bytes().to_vec();
+        let mut response = Self::new(version, response_code, body);
+        response.add_header(Header::CONTENT_TYPE, "text/plain");
+        response
+    }
     pub fn as_string(self) -> String {
-        let output_string: String = self.status_line.to_string();
-        let headers_string = self
-            .headers
-            .iter()
-            .map(|header: &Header| header.to_string().trim().to_string())
-            .collect::<Vec<String>>()
-            .join("\r\n");
-        let body_string = std::str::from_utf8(&self.body[..]).unwrap();
-        format!(
-            "{}\r\n{}\r\n\r\n{}",
-            output_string, headers_string, body_string
-        )
+        String::from_utf8_lossy(&self.to_bytes()).into_owned()
+    }
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head_string().into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let head = self.head_string();
+        let mut slices = [IoSlice::new(head.as_bytes()), IoSlice::new(&self.body)];
+        let mut slices = &mut slices[..];
+        while !slices.is_empty() {
+            match writer.write_vectored(slices) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(size) => IoSlice::advance_slices(&mut slices, size),
+                Err(error) if error.kind() == ErrorKind::Interrupted => (),
+                Err(error) => return Err(error),
+            }
+        }
+        writer.flush()
+    }
+    pub fn head_string(&self) -> String {
+        let mut head = format!("{}\r\n", self.status_line);
+        for header in &self.headers {
+            head += &format!("{}\r\n", header);
+        }
+        head + "\r\n"
     }
     pub fn add_header(&mut self, field_name: &str, field_value: &str) {
-        self.headers.push(Header::new(field_name, field_value));
+        self.headers.append(field_name, field_value);
+    }
+
+    pub fn response_code(&self) -> &ResponseCode {
+        &self.status_line.response_code
+    }
+    pub fn version(&self) -> &HttpVersion {
+        &self.status_line.version
+    }
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+    pub fn has_header(&self, field_name: &str) -> bool {
+        self.headers.contains_key(field_name)
+    }
+    pub fn header(&self, field_name: &str) -> Option<&str> {
+        self.headers.get(field_name)
+    }
+    pub fn set_header(&mut self, field_name: &str, field_value: &str) {
+        self.headers.insert(field_name, field_value);
+    }
+    pub fn remove_header(&mut self, field_name: &str) {
+        self.headers.remove(field_name);
+    }
+    pub fn set_version(&mut self, version: HttpVersion) {
+        self.status_line.version = version;
+    }
+    pub fn set_response_code(&mut self, response_code: ResponseCode) {
+        self.status_line.response_code = response_code;
+    }
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body;
+        self.stream = None;
+    }
+    pub fn set_stream(&mut self, stream: BodyStream) {
+        self.body.clear();
+        self.stream = Some(Box::new(stream));
+    }
+    pub fn strip_body(&mut self, version: &HttpVersion) {
+        match self.stream.take().map(|stream| stream.length()) {
+            Some(Some(length)) => self.set_header(Header::CONTENT_LENGTH, &length.to_string()),
+            Some(None) if version != &HttpVersion::HttpV1_0 => {
+                self.set_header(Header::TRANSFER_ENCODING, "chunked")
+            }
+            Some(None) => (),
+            None if !self.has_header(Header::CONTENT_LENGTH)
+                && self.response_code().allows_body() =>
+            {
+                let length = self.body.len().to_string();
+                self.add_header(Header::CONTENT_LENGTH, &length);
+            }
+            None => (),
+        }
+        self.body.clear();
+    }
+    pub fn is_streaming(&self) -> bool {
+        self.stream.is_some()
+    }
+    pub fn is_seekable(&self) -> bool {
+        self.stream.as_deref().is_some_and(BodyStream::is_seekable)
+    }
+    pub fn stream_length(&self) -> Option<u64> {
+        self.stream.as_deref().and_then(BodyStream::length)
+    }
+    pub fn take_stream(&mut self) -> Option<BodyStream> {
+        self.stream.take().map(|stream| *stream)
     }
 
     pub fn body_length(&self) -> usize {
@@ -404,7 +969,10 @@ impl Response {
 // tests
 #[cfg(test)]
 mod tests {
-    use super::{Header, HttpVersion, Method, RequestLine, ResponseCode, StatusLine};
+    use super::{
+        BodyStream, Header, HeaderMap, HttpVersion, Method, RequestLine, Response, ResponseCode,
+        StatusLine,
+    };
     use crate::types::Request;
 
     #[test]
@@ -415,17 +983,33 @@ mod tests {
         assert_eq!("PUT", Method::Put.to_string());
         assert_eq!("DELETE", Method::Delete.to_string());
         assert_eq!("OPTIONS", Method::Options.to_string());
+        assert_eq!("PATCH", Method::Patch.to_string());
+        assert_eq!(
+            "MKCOL",
+            Method::Extension(String::from("MKCOL")).to_string()
+        );
     }
 
     #[test]
     pub fn match_method() {
         let get = "GET";
-        let post = "Post";
-        let unknown = "Random";
+        let post = "POST";
+        let extension = "PROPFIND";
+        let lowercase = "Post";
+        let invalid = "GET/";
 
         assert_eq!(Some(Method::Get), Method::from_string(get));
         assert_eq!(Some(Method::Post), Method::from_string(post));
-        assert_eq!(None, Method::from_string(unknown));
+        assert_eq!(
+            Some(Method::Extension(String::from("PROPFIND"))),
+            Method::from_string(extension)
+        );
+        assert_eq!(
+            Some(Method::Extension(String::from("Post"))),
+            Method::from_string(lowercase)
+        );
+        assert_eq!(None, Method::from_string(invalid));
+        assert_eq!(None, Method::from_string(""));
     }
 
     #[test]
@@ -442,6 +1026,40 @@ mod tests {
     }
 
     #[test]
+    pub fn registered_and_custom_codes() {
+        assert_eq!(ResponseCode::new(201).to_string(), "201 Created");
+        assert_eq!(ResponseCode::new(308), ResponseCode::PermanentRedirect);
+        assert_eq!(ResponseCode::TooManyRequests.code(), 429);
+        assert_eq!(
+            ResponseCode::GatewayTimeout.reason_phrase(),
+            "Gateway Timeout"
+        );
+        assert!(ResponseCode::NoContent.is_success());
+        assert!(ResponseCode::Conflict.is_client_error());
+        assert!(!ResponseCode::Conflict.is_server_error());
+
+        let custom = ResponseCode::custom(499, "Client Closed Request").unwrap();
+        assert_eq!(
+            custom,
+            ResponseCode::Other(499, String::from("Client Closed Request"))
+        );
+        assert!(custom.is_client_error());
+        assert_eq!(
+            ResponseCode::custom(404, "Missing"),
+            Some(ResponseCode::NotFound)
+        );
+
+        assert_eq!(ResponseCode::new(599).to_string(), "599 Server Error");
+        let success = ResponseCode::custom(299, "").unwrap();
+        assert_eq!(success.to_string(), "299 Success");
+        assert_eq!(ResponseCode::custom(1000, "Too Big"), None);
+        assert_eq!(ResponseCode::custom(42, "Too Small"), None);
+        assert!(!ResponseCode::new(799).is_valid());
+        assert!(!ResponseCode::new(70000).is_valid());
+    }
+
+    #[test]
+    #[allow(clippy::unnecessary_to_owned)]
     pub fn parse_request_line() {
         let request_line_string = "GET /home HTTP/1.1 ";
         let request_line =
@@ -452,6 +1070,29 @@ mod tests {
         )
     }
 
+    #[test]
+    pub fn parse_http_versions() {
+        assert_eq!(
+            HttpVersion::from_string("HTTP/1.0"),
+            Some(HttpVersion::HttpV1_0)
+        );
+        assert_eq!(
+            HttpVersion::from_string("HTTP/1.7"),
+            Some(HttpVersion::HttpV1_1)
+        );
+        assert_eq!(HttpVersion::from_string("HTTP/1.10"), None);
+        assert_eq!(HttpVersion::from_string("HTTP/2"), None);
+
+        let request_line = RequestLine::from_string("GET / HTTP/1.9").unwrap();
+        assert_eq!(request_line.version, HttpVersion::HttpV1_1);
+        for version in ["HTTP/2.0", "HTTP/3.0", "HTTP/0.9"] {
+            let error = RequestLine::from_string(&format!("GET / {version}")).unwrap_err();
+            assert_eq!(error.response_code(), ResponseCode::HttpVersionNotSupported);
+        }
+        let error = RequestLine::from_string("GET / HTTP/1.x").unwrap_err();
+        assert_eq!(error.response_code(), ResponseCode::BadRequest);
+    }
+
     #[test]
     pub fn parse_status_line() {
         let status_line_string = "HTTP/1.1 200 OK";
@@ -472,11 +1113,73 @@ mod tests {
     }
 
     #[test]
+    pub fn binary_response_bytes() {
+        let body = vec![0x89, b'P', b'N', b'G', 0xff, 0x00];
+        let mut response = Response::new(HttpVersion::HttpV1_1, ResponseCode::Ok, body.clone());
+        response.add_header("Content-type", "image/png");
+        let mut head = b"HTTP/1.1 200 OK\r\nContent-type: image/png\r\n\r\n".to_vec();
+        head.extend_from_slice(&body);
+        assert_eq!(response.to_bytes(), head);
+
+        let mut written = Vec::new();
+        response.write_to(&mut written).unwrap();
+        assert_eq!(written, head);
+        assert!(response.as_string().starts_with("HTTP/1.1 200 OK"));
+    }
+
+    #[test]
+    pub fn strip_head_bodies() {
+        let streaming = || {
+            let mut response = Response::new(HttpVersion::HttpV1_1, ResponseCode::Ok, Vec::new());
+            response.set_stream(BodyStream::from_chunks(vec![b"abc".to_vec()]));
+            response
+        };
+        let mut response = streaming();
+        response.strip_body(&HttpVersion::HttpV1_0);
+        assert!(!response.is_streaming());
+        assert!(!response.has_header(Header::TRANSFER_ENCODING));
+        let mut response = streaming();
+        response.strip_body(&HttpVersion::HttpV1_1);
+        assert_eq!(response.header(Header::TRANSFER_ENCODING), Some("chunked"));
+
+        let mut response = Response::new(HttpVersion::HttpV1_1, ResponseCode::Ok, b"abc".to_vec());
+        response.strip_body(&HttpVersion::HttpV1_0);
+        assert_eq!(response.header(Header::CONTENT_LENGTH), Some("3"));
+        assert!(response.body().is_empty());
+    }
+
+    #[test]
+    pub fn header_map_lookup() {
+        let mut headers = HeaderMap::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append(Header::CONTENT_TYPE, "text/plain");
+        headers.append("set-cookie", "b=2");
+        assert_eq!(headers.get("content-type"), Some("text/plain"));
+        assert_eq!(
+            headers.get_all("SET-COOKIE").collect::<Vec<&str>>(),
+            vec!["a=1", "b=2"]
+        );
+
+        headers.insert("Set-Cookie", "c=3");
+        let names: Vec<&str> = headers.iter().map(Header::name).collect();
+        assert_eq!(names, vec!["Set-Cookie", "Content-Type"]);
+        assert_eq!(headers.get("set-cookie"), Some("c=3"));
+
+        assert_eq!(
+            headers.remove(Header::CONTENT_TYPE),
+            Some(String::from("text/plain"))
+        );
+        assert_eq!(headers.len(), 1);
+        assert!(headers.contains(&Header::new("set-cookie", "c=3")));
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison, clippy::unnecessary_to_owned)]
     pub fn parse_string_to_request() {
         const  REQUEST: &str = "GET / HTTP/1.1\r\nHost: localhost:50000\r\nConnection: keep-alive\r\nCache-Control: max-age=0\r\nsec-ch-ua: \"Not/A)Brand\";v=\"99\", \"Google Chrome\";v=\"115\", \"Chromium\";v=\"115\"\r\nsec-ch-ua-mobile: ?0\r\nsec-ch-ua-platform: \"macOS\"\r\nUpgrade-Insecure-Requests: 1\r\nUser-Agent: Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/115.0.0.0 Safari/537.36\r\nAccept: text/html,application/xhtml+xml,application/xml;q=0.9,image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;\r\n\r\nBody";
         let maybe_request = Request::parse_from_string(&REQUEST.to_string());
-        assert_eq!(maybe_request.is_none(), false);
-        assert_eq!(maybe_request.is_some(), true);
+        assert_eq!(maybe_request.is_err(), false);
+        assert_eq!(maybe_request.is_ok(), true);
         let request = maybe_request.unwrap();
         assert_eq!(request.request_line.version, HttpVersion::HttpV1_1);
         assert_eq!(request.request_line.method, Method::Get);
@@ -0,0 +1,318 @@
+use std::fmt::{self, Display};
+
+// Form
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Form {
+    Origin,
+    Absolute,
+    Authority,
+    Asterisk,
+}
+
+// Uri
+#[derive(Debug, Clone, PartialEq)]
+pub struct Uri {
+    form: Form,
+    scheme: Option<String>,
+    authority: Option<String>,
+    path: String,
+    query: Option<String>,
+}
+impl Uri {
+    pub fn parse(target: &str) -> Option<Self> {
+        if target == "*" {
+            return Some(Self::new(Form::Asterisk, None, None, "", None));
+        }
+        if !target.bytes().all(|byte| byte.is_ascii_graphic()) || !valid_escapes(target) {
+            return None;
+        }
+        let target = match target.split_once('#') {
+            Some((target, _fragment)) => target,
+            None => target,
+        };
+        let (target, query) = match target.split_once('?') {
+            Some((target, query)) => (target, Some(query)),
+            None => (target, None),
+        };
+        if target.starts_with('/') {
+            return Some(Self::new(Form::Origin, None, None, target, query));
+        }
+        if let Some((scheme, rest)) = target.split_once("://") {
+            let valid_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+            let (authority, path) = match rest.find('/') {
+                Some(index) => rest.split_at(index),
+                None => (rest, "/"),
+            };
+            if !valid_scheme || authority.is_empty() {
+                return None;
+            }
+            let scheme = scheme.to_ascii_lowercase();
+            return Some(Self::new(
+                Form::Absolute,
+                Some(&scheme),
+                Some(authority),
+                path,
+                query,
+            ));
+        }
+        let (host, port) = target.rsplit_once(':')?;
+        if query.is_some()
+            || host.is_empty()
+            || host.contains(['/', '@'])
+            || port.is_empty()
+            || !port.bytes().all(|byte| byte.is_ascii_digit())
+        {
+            return None;
+        }
+        Some(Self::new(Form::Authority, None, Some(target), "", None))
+    }
+
+    fn new(
+        form: Form,
+        scheme: Option<&str>,
+        authority: Option<&str>,
+        path: &str,
+        query: Option<&str>,
+    ) -> Self {
+        Self {
+            form,
+            scheme: scheme.map(String::from),
+            authority: authority.map(String::from),
+            path: path.to_string(),
+            query: query.map(String::from),
+        }
+    }
+
+    pub fn form(&self) -> Form {
+        self.form
+    }
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn query(&self) -> Option<&str> {
+        self.query.as_deref()
+    }
+
+    pub fn normalized_path(&self) -> String {
+        remove_dot_segments(&normalize_escapes(&self.path))
+    }
+    pub fn segments(&self) -> Vec<String> {
+        decoded_segments(&self.normalized_path())
+    }
+    pub fn path_segments(&self) -> Vec<String> {
+        decoded_segments(&self.path)
+    }
+    pub fn query_params(&self) -> Query {
+        Query::parse(self.query().unwrap_or_default())
+    }
+}
+impl Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.form {
+            Form::Asterisk => return write!(f, "*"),
+            Form::Authority => return write!(f, "{}", self.authority().unwrap_or_default()),
+            Form::Absolute => write!(
+                f,
+                "{}://{}",
+                self.scheme().unwrap_or_default(),
+                self.authority().unwrap_or_default()
+            )?,
+            Form::Origin => (),
+        }
+        write!(f, "{}", self.path)?;
+        match &self.query {
+            Some(query) => write!(f, "?{query}"),
+            None => Ok(()),
+        }
+    }
+}
+
+// Query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pairs: Vec<(String, String)>,
+}
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        let pairs = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode_form(name), decode_form(value))
+            })
+            .collect();
+        Self { pairs }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.pairs
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.pairs
+            .iter()
+            .filter(move |(param, _)| param == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match (bytes[index], hex_pair(&bytes[index + 1..])) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+pub fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    for (index, segment) in segments.iter().enumerate() {
+        let last = index + 1 == segments.len();
+        match *segment {
+            "." if last => output.push(""),
+            "." => (),
+            ".." => {
+                output.pop();
+                if last {
+                    output.push("");
+                }
+            }
+            segment => output.push(segment),
+        }
+    }
+    format!("/{}", output.join("/"))
+}
+
+fn decoded_segments(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(percent_decode)
+        .collect()
+}
+
+fn decode_form(value: &str) -> String {
+    percent_decode(&value.replace('+', " "))
+}
+
+fn normalize_escapes(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut normalized = String::with_capacity(path.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match (bytes[index], hex_pair(&bytes[index + 1..])) {
+            (b'%', Some(byte)) if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) => {
+                normalized.push(byte as char);
+                index += 3;
+            }
+            (b'%', Some(byte)) => {
+                normalized.push_str(&format!("%{byte:02X}"));
+                index += 3;
+            }
+            (byte, _) => {
+                normalized.push(byte as char);
+                index += 1;
+            }
+        }
+    }
+    normalized
+}
+
+fn valid_escapes(target: &str) -> bool {
+    let bytes = target.as_bytes();
+    bytes
+        .iter()
+        .enumerate()
+        .all(|(index, byte)| *byte != b'%' || hex_pair(&bytes[index + 1..]).is_some())
+}
+
+fn hex_pair(bytes: &[u8]) -> Option<u8> {
+    let digits = std::str::from_utf8(bytes.get(..2)?).ok()?;
+    if !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    u8::from_str_radix(digits, 16).ok()
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::{remove_dot_segments, Form, Uri};
+
+    #[test]
+    pub fn parse_request_target_forms() {
+        let origin = Uri::parse("/a%20b/./c/../d?x=1&y=2#top").unwrap();
+        assert_eq!(origin.form(), Form::Origin);
+        assert_eq!(origin.path(), "/a%20b/./c/../d");
+        assert_eq!(origin.query(), Some("x=1&y=2"));
+        assert_eq!(origin.normalized_path(), "/a%20b/d");
+        assert_eq!(origin.segments(), vec!["a b", "d"]);
+
+        let absolute = Uri::parse("HTTP://example.com:8080?q").unwrap();
+        assert_eq!(absolute.form(), Form::Absolute);
+        assert_eq!(absolute.scheme(), Some("http"));
+        assert_eq!(absolute.authority(), Some("example.com:8080"));
+        assert_eq!(absolute.path(), "/");
+        assert_eq!(absolute.to_string(), "http://example.com:8080/?q");
+
+        let authority = Uri::parse("example.com:443").unwrap();
+        assert_eq!(authority.form(), Form::Authority);
+        assert_eq!(authority.authority(), Some("example.com:443"));
+
+        assert_eq!(Uri::parse("*").unwrap().form(), Form::Asterisk);
+        assert_eq!(Uri::parse("/bad%2"), None);
+        assert_eq!(Uri::parse("index.html"), None);
+    }
+
+    #[test]
+    pub fn normalize_paths_and_queries() {
+        assert_eq!(remove_dot_segments("/a/b/c/./../../g"), "/a/g");
+        assert_eq!(remove_dot_segments("/../../x/"), "/x/");
+        assert_eq!(remove_dot_segments("/a/b/.."), "/a/");
+
+        let uri = Uri::parse("/%7euser/%2e%2e/%2fetc?tag=a+b&tag=c%26d&flag").unwrap();
+        assert_eq!(uri.normalized_path(), "/%2Fetc");
+        assert_eq!(uri.path_segments(), vec!["~user", "..", "/etc"]);
+        let query = uri.query_params();
+        assert_eq!(
+            query.get_all("tag").collect::<Vec<&str>>(),
+            vec!["a b", "c&d"]
+        );
+        assert_eq!(query.get("flag"), Some(""));
+        assert_eq!(query.get("missing"), None);
+    }
+}
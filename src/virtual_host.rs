@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+use crate::handler::Handler;
+use crate::headers::{Host, TypedHeader};
+use crate::parser::ParseError;
+use crate::static_files::StaticFiles;
+use crate::types::{Header, HttpVersion, Request, Response, ResponseCode};
+use crate::uri::Form;
+
+// HostPattern
+#[derive(Debug, Clone, PartialEq)]
+enum HostPattern {
+    Exact(String),
+    Wildcard(String),
+}
+impl HostPattern {
+    fn parse(pattern: &str) -> Self {
+        let pattern = normalize_hostname(pattern);
+        match pattern.strip_prefix("*.") {
+            Some(suffix) => Self::Wildcard(format!(".{suffix}")),
+            None => Self::Exact(pattern),
+        }
+    }
+
+    fn matches(&self, hostname: &str) -> bool {
+        match self {
+            Self::Exact(name) => name == hostname,
+            Self::Wildcard(suffix) => hostname.len() > suffix.len() && hostname.ends_with(suffix),
+        }
+    }
+}
+
+// VirtualHost
+struct VirtualHost {
+    pattern: HostPattern,
+    handler: Box<dyn Handler>,
+}
+
+// VirtualHosts
+#[derive(Default)]
+pub struct VirtualHosts {
+    hosts: Vec<VirtualHost>,
+    default_host: Option<Box<dyn Handler>>,
+}
+impl VirtualHosts {
+    pub fn new() -> Self {
+        Self {
+            hosts: Vec::new(),
+            default_host: None,
+        }
+    }
+
+    pub fn host<H: Handler>(&mut self, pattern: &str, handler: H) -> &mut Self {
+        self.hosts.push(VirtualHost {
+            pattern: HostPattern::parse(pattern),
+            handler: Box::new(handler),
+        });
+        self
+    }
+    pub fn root<P: Into<PathBuf>>(&mut self, pattern: &str, root: P) -> &mut Self {
+        self.host(pattern, StaticFiles::new(root))
+    }
+    pub fn default_host<H: Handler>(&mut self, handler: H) -> &mut Self {
+        self.default_host = Some(Box::new(handler));
+        self
+    }
+
+    fn find(&self, hostname: &str) -> Option<&dyn Handler> {
+        let exact = self.hosts.iter().find(|host| {
+            matches!(host.pattern, HostPattern::Exact(_)) && host.pattern.matches(hostname)
+        });
+        let wildcard = || {
+            self.hosts
+                .iter()
+                .filter(|host| host.pattern.matches(hostname))
+                .max_by_key(|host| match &host.pattern {
+                    HostPattern::Wildcard(suffix) => suffix.len(),
+                    HostPattern::Exact(_) => 0,
+                })
+        };
+        exact
+            .or_else(wildcard)
+            .map(|host| host.handler.as_ref())
+            .or(self.default_host.as_deref())
+    }
+}
+impl Handler for VirtualHosts {
+    fn handle(&self, request: Request) -> Response {
+        let hostname = request_hostname(&request).unwrap_or_default();
+        match self.find(&hostname) {
+            Some(handler) => handler.handle(request),
+            None => Response::from_code(
+                request.request_line.version,
+                ResponseCode::MisdirectedRequest,
+            ),
+        }
+    }
+}
+
+pub fn validate_host(request: &Request) -> Result<(), ParseError> {
+    let values: Vec<&str> = request.headers.get_all(Header::HOST).collect();
+    match values[..] {
+        [] if request.request_line.version == HttpVersion::HttpV1_0 => Ok(()),
+        [] => Err(ParseError::MissingHost),
+        [value] if value.trim().is_empty() || Host::parse(value).is_some() => Ok(()),
+        [value] => Err(ParseError::InvalidHost(value.to_string())),
+        _ => Err(ParseError::InvalidHost(values.join(", "))),
+    }
+}
+
+pub fn request_hostname(request: &Request) -> Option<String> {
+    let uri = request.uri();
+    let authority = match uri.as_ref() {
+        Some(uri) if uri.form() == Form::Absolute => uri.authority().map(String::from),
+        _ => request.header(Header::HOST).map(String::from),
+    }?;
+    let authority = authority.rsplit('@').next().unwrap_or_default();
+    Host::parse(authority).map(|host| normalize_hostname(&host.hostname))
+}
+
+fn normalize_hostname(hostname: &str) -> String {
+    hostname.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::{validate_host, VirtualHosts};
+    use crate::handler::Handler;
+    use crate::parser::ParseError;
+    use crate::types::{test_request, Request, Response, ResponseCode};
+
+    fn site(name: &'static str) -> impl Fn(Request) -> Response + Send + Sync {
+        move |request: Request| {
+            Response::new(
+                request.request_line.version,
+                ResponseCode::Ok,
+                name.as_bytes().to_vec(),
+            )
+        }
+    }
+
+    #[test]
+    pub fn validate_host_header() {
+        assert_eq!(
+            validate_host(&test_request("GET", "/", &[("Host", "example.com:8080")])),
+            Ok(())
+        );
+        assert_eq!(
+            validate_host(&test_request("GET", "/", &[("Host", "example.com:")])),
+            Ok(())
+        );
+        assert_eq!(
+            validate_host(&test_request("GET", "/", &[])),
+            Err(ParseError::MissingHost)
+        );
+        assert!(matches!(
+            validate_host(&test_request(
+                "GET",
+                "/",
+                &[("Host", "a.com"), ("Host", "b.com")]
+            )),
+            Err(ParseError::InvalidHost(_))
+        ));
+        assert!(matches!(
+            validate_host(&test_request("GET", "/", &[("Host", "a.com/path")])),
+            Err(ParseError::InvalidHost(_))
+        ));
+        assert!(matches!(
+            validate_host(&test_request("GET", "/", &[("Host", "[::1]garbage")])),
+            Err(ParseError::InvalidHost(_))
+        ));
+        let legacy = Request::parse_from_string("GET / HTTP/1.0\r\n\r\n").unwrap();
+        assert_eq!(validate_host(&legacy), Ok(()));
+    }
+
+    #[test]
+    pub fn dispatch_by_host() {
+        let mut hosts = VirtualHosts::new();
+        hosts
+            .host("docs.example.com", site("docs"))
+            .host("*.example.com", site("wildcard"))
+            .host("*.api.example.com", site("api"));
+
+        let response = hosts.handle(test_request(
+            "GET",
+            "/",
+            &[("Host", "Docs.Example.com.:8080")],
+        ));
+        assert_eq!(response.body(), b"docs");
+        let response = hosts.handle(test_request("GET", "/", &[("Host", "www.example.com")]));
+        assert_eq!(response.body(), b"wildcard");
+        let response = hosts.handle(test_request("GET", "/", &[("Host", "v1.api.example.com")]));
+        assert_eq!(response.body(), b"api");
+        let response = hosts.handle(test_request(
+            "GET",
+            "http://docs.example.com/",
+            &[("Host", "other.org")],
+        ));
+        assert_eq!(response.body(), b"docs");
+
+        let response = hosts.handle(test_request("GET", "/", &[("Host", "example.com")]));
+        assert_eq!(response.response_code(), &ResponseCode::MisdirectedRequest);
+        hosts.default_host(site("default"));
+        let response = hosts.handle(test_request("GET", "/", &[("Host", "other.org")]));
+        assert_eq!(response.body(), b"default");
+    }
+}